@@ -0,0 +1,73 @@
+use glm::{Mat4, Vec3, Vec4};
+
+// Cámara con proyección en perspectiva
+pub struct Camera {
+   pub eye: Vec3,     // Posición de la cámara
+   pub target: Vec3,  // Punto hacia el que mira
+   pub up: Vec3,      // Dirección "arriba" de la cámara
+   pub fov: f32,      // Campo de visión vertical (radianes)
+   pub near: f32,     // Plano cercano
+   pub far: f32,      // Plano lejano
+   pub aspect: f32,   // Relación de aspecto (ancho / alto)
+}
+
+impl Camera {
+   pub fn new(eye: Vec3, target: Vec3, up: Vec3, fov: f32, aspect: f32) -> Self {
+      Camera {
+         eye,
+         target,
+         up,
+         fov,
+         near: 0.1,
+         far: 100.0,
+         aspect,
+      }
+   }
+
+   // Ajustar la proyección a un área de `width` x `height` pixeles. `fov` es el
+   // campo de visión vertical para áreas al menos tan anchas como `min_aspect`;
+   // en las más angostas se abre el campo vertical para conservar el
+   // horizontal, así lo que entraba a lo ancho sigue entrando.
+   pub fn fit_viewport(&mut self, width: usize, height: usize, fov: f32, min_aspect: f32) {
+      self.aspect = width as f32 / height as f32;
+      self.fov = if self.aspect >= min_aspect {
+         fov
+      } else {
+         2.0 * ((fov * 0.5).tan() * min_aspect / self.aspect).atan()
+      };
+   }
+
+   // Matriz de vista: lleva puntos del mundo al espacio de la cámara
+   pub fn view_matrix(&self) -> Mat4 {
+      glm::ext::look_at(self.eye, self.target, self.up)
+   }
+
+   // Matriz de proyección en perspectiva (espacio de cámara a espacio de recorte)
+   pub fn projection_matrix(&self) -> Mat4 {
+      glm::ext::perspective(self.fov, self.aspect, self.near, self.far)
+   }
+
+   // Ejes de la vista expresados en el mundo: derecha y arriba de la pantalla,
+   // y adelante, hacia donde mira la cámara
+   pub fn basis(&self) -> (Vec3, Vec3, Vec3) {
+      let forward = glm::normalize(self.target - self.eye);
+      let right = glm::normalize(glm::cross(forward, self.up));
+      let up = glm::cross(right, forward);
+      (right, up, forward)
+   }
+
+   pub fn view_projection_matrix(&self) -> Mat4 {
+      self.projection_matrix() * self.view_matrix()
+   }
+}
+
+// Matriz de viewport: lleva coordenadas normalizadas (NDC) a pixeles.
+// X va de 0 a width, Y crece hacia abajo y la profundidad queda en [0, 1].
+pub fn viewport_matrix(width: f32, height: f32) -> Mat4 {
+   Mat4::new(
+      Vec4::new(width * 0.5, 0.0, 0.0, 0.0),
+      Vec4::new(0.0, -height * 0.5, 0.0, 0.0),
+      Vec4::new(0.0, 0.0, 0.5, 0.0),
+      Vec4::new(width * 0.5, height * 0.5, 0.5, 1.0),
+   )
+}
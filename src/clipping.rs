@@ -0,0 +1,231 @@
+use glm::Vec4;
+use crate::triangle::Varyings;
+
+// Vértice en espacio de recorte junto con los atributos que se interpolan
+#[derive(Debug, Clone, Copy)]
+pub struct ClipVertex {
+   pub position: Vec4,
+   pub varyings: Varyings,
+}
+
+impl ClipVertex {
+   fn lerp(&self, other: &ClipVertex, t: f32) -> ClipVertex {
+      ClipVertex {
+         position: lerp(&self.position, &other.position, t),
+         varyings: self.varyings.lerp(&other.varyings, t),
+      }
+   }
+}
+
+// Planos del volumen de visión. En espacio de recorte un punto está dentro
+// cuando -w <= x, y, z <= w.
+#[derive(Clone, Copy)]
+enum Plane {
+   Left,
+   Right,
+   Bottom,
+   Top,
+   Near,
+   Far,
+}
+
+const PLANES: [Plane; 6] = [
+   Plane::Near,
+   Plane::Far,
+   Plane::Left,
+   Plane::Right,
+   Plane::Bottom,
+   Plane::Top,
+];
+
+impl Plane {
+   // Distancia con signo al plano: positiva (o cero) si el punto está dentro
+   fn distance(self, v: &Vec4) -> f32 {
+      match self {
+         Plane::Left => v.w + v.x,
+         Plane::Right => v.w - v.x,
+         Plane::Bottom => v.w + v.y,
+         Plane::Top => v.w - v.y,
+         Plane::Near => v.w + v.z,
+         Plane::Far => v.w - v.z,
+      }
+   }
+}
+
+fn lerp(a: &Vec4, b: &Vec4, t: f32) -> Vec4 {
+   Vec4::new(
+      a.x + (b.x - a.x) * t,
+      a.y + (b.y - a.y) * t,
+      a.z + (b.z - a.z) * t,
+      a.w + (b.w - a.w) * t,
+   )
+}
+
+fn is_inside(v: &Vec4) -> bool {
+   PLANES.iter().all(|plane| plane.distance(v) >= 0.0)
+}
+
+// Recortar un triángulo en espacio de recorte contra los seis planos del
+// volumen de visión (Sutherland–Hodgman). Los atributos de los vértices nuevos
+// se interpolan a lo largo de la arista cortada. Devuelve el polígono
+// resultante, que queda vacío si el triángulo está completamente fuera.
+pub fn clip_triangle(v0: ClipVertex, v1: ClipVertex, v2: ClipVertex) -> Vec<ClipVertex> {
+   // Caso común: el triángulo está completamente dentro
+   if is_inside(&v0.position) && is_inside(&v1.position) && is_inside(&v2.position) {
+      return vec![v0, v1, v2];
+   }
+
+   let mut polygon = vec![v0, v1, v2];
+
+   for plane in PLANES {
+      if polygon.is_empty() {
+         break;
+      }
+
+      let input = std::mem::take(&mut polygon);
+      for i in 0..input.len() {
+         let current = &input[i];
+         let next = &input[(i + 1) % input.len()];
+
+         let d_current = plane.distance(&current.position);
+         let d_next = plane.distance(&next.position);
+
+         if d_current >= 0.0 {
+            polygon.push(*current);
+         }
+
+         // La arista cruza el plano: agregar el punto de intersección
+         if (d_current >= 0.0) != (d_next >= 0.0) {
+            let t = d_current / (d_current - d_next);
+            polygon.push(current.lerp(next, t));
+         }
+      }
+   }
+
+   // Un polígono con menos de tres vértices no tiene área
+   if polygon.len() < 3 {
+      polygon.clear();
+   }
+
+   polygon
+}
+
+// Dividir un polígono convexo en triángulos (abanico desde el primer vértice)
+pub fn triangulate<T: Copy>(polygon: &[T]) -> impl Iterator<Item = [T; 3]> + '_ {
+   (1..polygon.len().saturating_sub(1)).map(move |i| [polygon[0], polygon[i], polygon[i + 1]])
+}
+
+// Recortar un segmento contra el volumen de visión (Liang–Barsky en
+// coordenadas homogéneas). Devuelve None si el segmento queda fuera.
+pub fn clip_line(a: Vec4, b: Vec4) -> Option<(Vec4, Vec4)> {
+   let mut t0: f32 = 0.0;
+   let mut t1: f32 = 1.0;
+
+   for plane in PLANES {
+      let d_a = plane.distance(&a);
+      let d_b = plane.distance(&b);
+
+      if d_a < 0.0 && d_b < 0.0 {
+         return None;
+      }
+
+      if d_a < 0.0 {
+         t0 = t0.max(d_a / (d_a - d_b));
+      } else if d_b < 0.0 {
+         t1 = t1.min(d_a / (d_a - d_b));
+      }
+
+      if t0 > t1 {
+         return None;
+      }
+   }
+
+   Some((lerp(&a, &b, t0), lerp(&a, &b, t1)))
+}
+
+#[cfg(test)]
+mod tests {
+   use glm::{Vec2, Vec3, Vec4};
+   use crate::triangle::Varyings;
+   use super::{clip_line, clip_triangle, triangulate, ClipVertex};
+
+   // Vértice con w = 1 que guarda su posición también como atributo, para
+   // comprobar que los vértices nuevos se interpolan bien
+   fn vertex(x: f32, y: f32, z: f32) -> ClipVertex {
+      let position = Vec3::new(x, y, z);
+      ClipVertex {
+         position: Vec4::new(x, y, z, 1.0),
+         varyings: Varyings {
+            world_position: position,
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            color: position,
+            uv: Vec2::new(x, y),
+            custom: Vec4::new(0.0, 0.0, 0.0, 0.0),
+         },
+      }
+   }
+
+   fn assert_inside(polygon: &[ClipVertex]) {
+      for v in polygon {
+         let p = v.position;
+         assert!(
+            [p.x, p.y, p.z].iter().all(|c| c.abs() <= p.w + 1e-5),
+            "vértice ({}, {}, {}, {}) fuera del volumen", p.x, p.y, p.z, p.w,
+         );
+         let attribute = v.varyings.world_position;
+         assert!(
+            (attribute.x - p.x).abs() < 1e-5 && (attribute.y - p.y).abs() < 1e-5 && (attribute.z - p.z).abs() < 1e-5,
+            "atributo ({}, {}, {}) mal interpolado", attribute.x, attribute.y, attribute.z,
+         );
+      }
+   }
+
+   #[test]
+   fn one_vertex_behind_near_plane_gives_a_quad() {
+      let polygon = clip_triangle(vertex(0.0, 0.5, -3.0), vertex(-0.5, -0.5, 0.0), vertex(0.5, -0.5, 0.0));
+      assert_eq!(polygon.len(), 4);
+      assert_inside(&polygon);
+      assert_eq!(triangulate(&polygon).count(), 2);
+   }
+
+   #[test]
+   fn two_vertices_behind_near_plane_give_a_triangle() {
+      let polygon = clip_triangle(vertex(0.0, 0.5, -3.0), vertex(-0.5, -0.5, -3.0), vertex(0.5, -0.5, 0.0));
+      assert_eq!(polygon.len(), 3);
+      assert_inside(&polygon);
+      assert_eq!(triangulate(&polygon).count(), 1);
+   }
+
+   #[test]
+   fn triangle_outside_is_discarded() {
+      // Cada vértice está fuera de un plano distinto, pero ninguno de los
+      // puntos del triángulo queda dentro
+      let polygon = clip_triangle(vertex(3.0, 0.0, 0.0), vertex(0.0, 3.0, 0.0), vertex(3.0, 3.0, 0.0));
+      assert!(polygon.is_empty());
+      assert_eq!(triangulate(&polygon).count(), 0);
+   }
+
+   #[test]
+   fn lines_are_clipped_at_their_endpoints() {
+      let point = |x: f32, y: f32, z: f32| Vec4::new(x, y, z, 1.0);
+      let same = |a: Vec4, b: Vec4| (a.x - b.x).abs() < 1e-5 && (a.y - b.y).abs() < 1e-5 && (a.z - b.z).abs() < 1e-5;
+
+      // Dentro: no cambia
+      let (a, b) = clip_line(point(-0.5, 0.0, 0.0), point(0.5, 0.5, 0.0)).unwrap();
+      assert!(same(a, point(-0.5, 0.0, 0.0)) && same(b, point(0.5, 0.5, 0.0)));
+
+      // Un extremo detrás del plano cercano: se mueve hasta el plano
+      let (a, b) = clip_line(point(0.0, 0.0, -3.0), point(0.0, 0.0, 1.0)).unwrap();
+      assert!(same(a, point(0.0, 0.0, -1.0)) && same(b, point(0.0, 0.0, 1.0)));
+
+      // Cruza de lado a lado: se recortan los dos extremos
+      let (a, b) = clip_line(point(-2.0, 0.0, 0.0), point(2.0, 0.0, 0.0)).unwrap();
+      assert!(same(a, point(-1.0, 0.0, 0.0)) && same(b, point(1.0, 0.0, 0.0)));
+
+      // Los dos extremos fuera del mismo plano
+      assert!(clip_line(point(2.0, 0.0, 0.0), point(3.0, 0.5, 0.0)).is_none());
+
+      // Fuera de planos distintos, pasando junto a una esquina del volumen
+      assert!(clip_line(point(0.0, 3.0, 0.0), point(3.0, 0.0, 0.0)).is_none());
+   }
+}
@@ -0,0 +1,59 @@
+use std::time::{Duration, Instant};
+
+// Paso máximo de tiempo por cuadro. Si un cuadro tarda mucho (por ejemplo al
+// arrastrar la ventana) la animación no da un salto enorme.
+const MAX_DELTA: f32 = 0.25;
+
+// Reloj del bucle principal: mide cuánto pasó entre cuadros y espera lo justo
+// para no superar los cuadros por segundo pedidos
+pub struct FrameClock {
+   target: Duration,
+   last_tick: Instant,
+   delta: f32,
+   // Promedio móvil de la duración de los cuadros, en segundos
+   average_delta: f32,
+}
+
+impl FrameClock {
+   pub fn new(fps: f32) -> Self {
+      FrameClock {
+         target: Duration::from_secs_f32(1.0 / fps),
+         last_tick: Instant::now(),
+         delta: 0.0,
+         average_delta: 0.0,
+      }
+   }
+
+   // Empezar un cuadro nuevo y devolver los segundos desde el anterior
+   pub fn tick(&mut self) -> f32 {
+      let now = Instant::now();
+      self.delta = now.duration_since(self.last_tick).as_secs_f32().min(MAX_DELTA);
+      self.last_tick = now;
+
+      self.average_delta = if self.average_delta == 0.0 {
+         self.delta
+      } else {
+         self.average_delta * 0.95 + self.delta * 0.05
+      };
+      self.delta
+   }
+
+   // Segundos que duró el último cuadro
+   pub fn delta(&self) -> f32 {
+      self.delta
+   }
+
+   // Cuadros por segundo, promediados para que el número no salte
+   pub fn fps(&self) -> f32 {
+      if self.average_delta > 0.0 { 1.0 / self.average_delta } else { 0.0 }
+   }
+
+   // Dormir lo que falte para completar el cuadro. Si dibujar ya tomó más
+   // tiempo no se espera nada.
+   pub fn wait(&self) {
+      let elapsed = self.last_tick.elapsed();
+      if elapsed < self.target {
+         std::thread::sleep(self.target - elapsed);
+      }
+   }
+}
@@ -1,4 +1,15 @@
-#[derive(Debug, Clone, Copy)]
+use std::sync::OnceLock;
+use glm::Vec3;
+
+// Entradas de la tabla para codificar luz lineal en sRGB: con tantas, el
+// error de la tabla es mucho menor que un nivel de 8 bits también en las
+// sombras, donde la curva es más empinada
+const ENCODE_TABLE_SIZE: usize = 1 << 16;
+
+// `repr(C)` garantiza que un `[Color]` ocupa 3 bytes por pixel (R, G, B) sin
+// relleno, para subirlo a la pantalla como RGB24 sin convertirlo
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
 pub struct Color {
    pub r: u8,
    pub g: u8,
@@ -9,4 +20,60 @@ impl Color {
    pub fn new(r: u8, g: u8, b: u8) -> Self {
       Color { r, g, b }
    }
-}
\ No newline at end of file
+
+   // Convertir desde componentes en [0, 1], recortando lo que se salga del rango
+   pub fn from_vec3(v: Vec3) -> Self {
+      let to_u8 = |c: f32| (c.clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+      Color::new(to_u8(v.x), to_u8(v.y), to_u8(v.z))
+   }
+
+   // Mezclar `other` sobre este color con la opacidad dada (0 = transparente)
+   pub fn blend(self, other: Color, alpha: f32) -> Color {
+      let mix = |a: u8, b: u8| (a as f32 * (1.0 - alpha) + b as f32 * alpha + 0.5) as u8;
+      Color::new(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b))
+   }
+
+   // Componentes normalizadas en [0, 1]
+   pub fn to_vec3(self) -> Vec3 {
+      Vec3::new(self.r as f32 / 255.0, self.g as f32 / 255.0, self.b as f32 / 255.0)
+   }
+
+   // Color lineal (luz) de este color sRGB, con componentes en [0, 1]
+   pub fn to_linear(self) -> Vec3 {
+      let table = decode_table();
+      Vec3::new(table[self.r as usize], table[self.g as usize], table[self.b as usize])
+   }
+
+   // Codificar en sRGB una luz lineal, recortando lo que se salga de [0, 1]
+   pub fn from_linear(v: Vec3) -> Self {
+      let table = encode_table();
+      let encode = |c: f32| table[(c.clamp(0.0, 1.0) * (ENCODE_TABLE_SIZE - 1) as f32 + 0.5) as usize];
+      Color::new(encode(v.x), encode(v.y), encode(v.z))
+   }
+}
+
+// Curva de transferencia sRGB: de un valor de pantalla en [0, 1] a luz lineal
+pub fn srgb_to_linear(c: f32) -> f32 {
+   if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+// Y de luz lineal en [0, 1] a valor de pantalla
+pub fn linear_to_srgb(c: f32) -> f32 {
+   if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
+
+// Las curvas se calculan una vez en tablas, porque con HDR se aplican a cada
+// pixel en cada cuadro
+fn decode_table() -> &'static [f32; 256] {
+   static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+   TABLE.get_or_init(|| std::array::from_fn(|i| srgb_to_linear(i as f32 / 255.0)))
+}
+
+fn encode_table() -> &'static [u8] {
+   static TABLE: OnceLock<Vec<u8>> = OnceLock::new();
+   TABLE.get_or_init(|| {
+      (0..ENCODE_TABLE_SIZE)
+         .map(|i| (linear_to_srgb(i as f32 / (ENCODE_TABLE_SIZE - 1) as f32) * 255.0 + 0.5) as u8)
+         .collect()
+   })
+}
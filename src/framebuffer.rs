@@ -1,5 +1,10 @@
+use glm::Vec3;
 use crate::color::Color;
+use crate::hdr::ToneMapper;
+use crate::image::Image;
+use crate::msaa::Msaa;
 
+// Resolución por defecto de la ventana y de las imágenes generadas
 pub const SCREEN_WIDTH: usize = 800;
 pub const SCREEN_HEIGHT: usize = 600;
 
@@ -8,17 +13,155 @@ pub struct Framebuffer {
    pub z_buffer: Vec<f32>,
    pub width: usize,
    pub height: usize,
+   // Con MSAA los triángulos se dibujan en las muestras (las de cada pixel
+   // seguidas) y `resolve` las junta en `buffer` y `z_buffer`
+   pub msaa: Msaa,
+   pub sample_buffer: Vec<Color>,
+   pub sample_z_buffer: Vec<f32>,
+   // Con HDR los triángulos se dibujan como luz lineal sin tope, una por
+   // muestra (o por pixel sin MSAA), y `resolve_hdr` la lleva a `buffer`
+   pub hdr: bool,
+   pub hdr_buffer: Vec<Vec3>,
 }
 
 impl Framebuffer {
-   pub fn new() -> Self {
-      let buffer = vec![Color::new(0, 0, 0); SCREEN_WIDTH * SCREEN_HEIGHT];
-      let z_buffer = vec![f32::INFINITY; SCREEN_WIDTH * SCREEN_HEIGHT];
+   pub fn new(width: usize, height: usize) -> Self {
+      let buffer = vec![Color::new(0, 0, 0); width * height];
+      let z_buffer = vec![f32::INFINITY; width * height];
       Framebuffer {
          buffer,
          z_buffer,
-         width: SCREEN_WIDTH,
-         height: SCREEN_HEIGHT,
+         width,
+         height,
+         msaa: Msaa::Off,
+         sample_buffer: Vec::new(),
+         sample_z_buffer: Vec::new(),
+         hdr: false,
+         hdr_buffer: Vec::new(),
+      }
+   }
+
+   // Cambiar la resolución. El contenido anterior se descarta, así que hay que
+   // volver a dibujar el cuadro.
+   pub fn resize(&mut self, width: usize, height: usize) {
+      if width == self.width && height == self.height {
+         return;
+      }
+      let (msaa, hdr) = (self.msaa, self.hdr);
+      *self = Framebuffer::new(width, height);
+      self.set_msaa(msaa);
+      self.set_hdr(hdr);
+   }
+
+   // Cambiar la cantidad de muestras por pixel. Las muestras se reservan solo
+   // con MSAA y su contenido queda indefinido hasta `begin_samples`.
+   pub fn set_msaa(&mut self, msaa: Msaa) {
+      self.msaa = msaa;
+      self.allocate();
+   }
+
+   // Dibujar los triángulos en punto flotante. El buffer HDR queda indefinido
+   // hasta `begin_hdr`.
+   pub fn set_hdr(&mut self, hdr: bool) {
+      self.hdr = hdr;
+      self.allocate();
+   }
+
+   fn allocate(&mut self) {
+      let samples = self.width * self.height * self.msaa.samples();
+      let len = if self.msaa == Msaa::Off { 0 } else { samples };
+      self.sample_buffer.resize(len, Color::new(0, 0, 0));
+      self.sample_z_buffer.resize(len, f32::MAX);
+      self.hdr_buffer.resize(if self.hdr { samples } else { 0 }, Vec3::new(0.0, 0.0, 0.0));
+   }
+
+   // Donde dibuja el rasterizador: las muestras con MSAA y si no los mismos
+   // buffers del framebuffer
+   pub fn samples(&self) -> (&[Color], &[f32]) {
+      if self.msaa == Msaa::Off {
+         (&self.buffer, &self.z_buffer)
+      } else {
+         (&self.sample_buffer, &self.sample_z_buffer)
+      }
+   }
+
+   pub fn samples_mut(&mut self) -> (&mut [Color], &mut [f32]) {
+      if self.msaa == Msaa::Off {
+         (&mut self.buffer, &mut self.z_buffer)
+      } else {
+         (&mut self.sample_buffer, &mut self.sample_z_buffer)
+      }
+   }
+
+   // Donde dibuja el rasterizador con HDR: la luz de cada muestra y su
+   // profundidad
+   pub fn hdr_samples(&self) -> (&[Vec3], &[f32]) {
+      let depths = if self.msaa == Msaa::Off { &self.z_buffer } else { &self.sample_z_buffer };
+      (&self.hdr_buffer, depths)
+   }
+
+   pub fn hdr_samples_mut(&mut self) -> (&mut [Vec3], &mut [f32]) {
+      let depths = if self.msaa == Msaa::Off { &mut self.z_buffer } else { &mut self.sample_z_buffer };
+      (&mut self.hdr_buffer, depths)
+   }
+
+   // Empezar a dibujar con MSAA: cada muestra toma el color de su pixel (el
+   // fondo) y la profundidad máxima
+   pub fn begin_samples(&mut self) {
+      let samples = self.msaa.samples();
+      if samples == 1 {
+         return;
+      }
+      for (pixel, chunk) in self.buffer.iter().zip(self.sample_buffer.chunks_exact_mut(samples)) {
+         chunk.fill(*pixel);
+      }
+      self.sample_z_buffer.fill(f32::MAX);
+   }
+
+   // Empezar a dibujar con HDR: cada muestra toma la luz que con `tone_mapper`
+   // se ve igual que el color de su pixel (el fondo) y la profundidad máxima
+   pub fn begin_hdr(&mut self, tone_mapper: &ToneMapper) {
+      let samples = self.msaa.samples();
+      for (pixel, chunk) in self.buffer.iter().zip(self.hdr_buffer.chunks_exact_mut(samples)) {
+         chunk.fill(tone_mapper.unmap(pixel.to_linear()));
+      }
+      self.sample_z_buffer.fill(f32::MAX);
+   }
+
+   // Promediar las muestras de cada pixel en `buffer`. En `z_buffer` queda la
+   // muestra más cercana, contra la que se prueban las líneas.
+   pub fn resolve(&mut self) {
+      let samples = self.msaa.samples();
+      if samples == 1 {
+         return;
+      }
+      for (pixel, colors) in self.buffer.iter_mut().zip(self.sample_buffer.chunks_exact(samples)) {
+         let (r, g, b) = colors.iter().fold((0, 0, 0), |(r, g, b), c| (r + c.r as usize, g + c.g as usize, b + c.b as usize));
+         let average = |sum: usize| ((sum + samples / 2) / samples) as u8;
+         *pixel = Color::new(average(r), average(g), average(b));
+      }
+      self.resolve_depth();
+   }
+
+   // Pasar la imagen HDR a `buffer`: cada muestra se comprime con
+   // `tone_mapper` y el promedio de cada pixel se codifica en sRGB.
+   // Promediar después de comprimir evita que una muestra muy brillante
+   // tape a las demás en los bordes.
+   pub fn resolve_hdr(&mut self, tone_mapper: &ToneMapper) {
+      let samples = self.msaa.samples();
+      for (pixel, radiance) in self.buffer.iter_mut().zip(self.hdr_buffer.chunks_exact(samples)) {
+         let sum = radiance.iter().fold(Vec3::new(0.0, 0.0, 0.0), |sum, r| sum + tone_mapper.map(*r));
+         *pixel = Color::from_linear(sum / samples as f32);
+      }
+      if samples > 1 {
+         self.resolve_depth();
+      }
+   }
+
+   fn resolve_depth(&mut self) {
+      let samples = self.msaa.samples();
+      for (z, depths) in self.z_buffer.iter_mut().zip(self.sample_z_buffer.chunks_exact(samples)) {
+         *z = depths.iter().copied().fold(f32::MAX, f32::min);
       }
    }
 
@@ -39,32 +182,34 @@ impl Framebuffer {
       }
    }
 
-   pub fn render(&self, renderer: &mut sdl2::render::Canvas<sdl2::video::Window>) {
-      let creator = renderer.texture_creator();
-      let mut texture = creator
-         .create_texture_streaming(
-               sdl2::pixels::PixelFormatEnum::RGB24,
-               self.width as u32,
-               self.height as u32,
-         )
-         .unwrap();
-
-      // Actualizar la textura con nuestro framebuffer
-      texture
-         .with_lock(None, |buffer: &mut [u8], pitch: usize| {
-               for y in 0..self.height {
-                  for x in 0..self.width {
-                     let offset = y * pitch + x * 3;
-                     let index = y * self.width + x;
-                     buffer[offset] = self.buffer[index].r;
-                     buffer[offset + 1] = self.buffer[index].g;
-                     buffer[offset + 2] = self.buffer[index].b;
-                  }
-               }
-         })
-         .unwrap();
-
-      renderer.copy(&texture, None, None).unwrap();
-      renderer.present();
-   }
-}
\ No newline at end of file
+   // Pintar un rectángulo con la opacidad dada (1 = tapa lo que había), por
+   // ejemplo como fondo de un texto. La parte fuera de la pantalla se ignora.
+   pub fn fill_rect(&mut self, x: i32, y: i32, width: usize, height: usize, color: Color, alpha: f32) {
+      let x0 = x.clamp(0, self.width as i32) as usize;
+      let y0 = y.clamp(0, self.height as i32) as usize;
+      let x1 = (x + width as i32).clamp(0, self.width as i32) as usize;
+      let y1 = (y + height as i32).clamp(0, self.height as i32) as usize;
+      for row in y0..y1 {
+         for pixel in &mut self.buffer[row * self.width + x0..row * self.width + x1] {
+            *pixel = if alpha >= 1.0 { color } else { pixel.blend(color, alpha) };
+         }
+      }
+   }
+
+   // Copia del contenido actual, para guardarlo en un archivo
+   pub fn to_image(&self) -> Image {
+      Image {
+         width: self.width,
+         height: self.height,
+         pixels: self.buffer.clone(),
+      }
+   }
+
+   // Los pixeles como bytes RGB24 contiguos, fila por fila, sin copiarlos
+   pub fn as_bytes(&self) -> &[u8] {
+      const _: () = assert!(std::mem::size_of::<Color>() == 3 && std::mem::align_of::<Color>() == 1);
+      // Seguro: Color es repr(C) con tres u8, así que el búfer es un bloque de
+      // 3 * len bytes inicializados con la misma duración que `self`
+      unsafe { std::slice::from_raw_parts(self.buffer.as_ptr() as *const u8, self.buffer.len() * 3) }
+   }
+}
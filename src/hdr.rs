@@ -0,0 +1,103 @@
+use glm::Vec3;
+
+// Curva que comprime la luz sin tope de la escena al rango de la pantalla
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToneMapping {
+   // x / (1 + x): conserva bien los tonos medios y nunca llega al blanco
+   Reinhard,
+   // Aproximación de Narkowicz a la curva fílmica de ACES: más contraste y
+   // las luces muy fuertes sí terminan en blanco
+   Aces,
+}
+
+impl ToneMapping {
+   pub fn name(self) -> &'static str {
+      match self {
+         ToneMapping::Reinhard => "Reinhard",
+         ToneMapping::Aces => "ACES",
+      }
+   }
+
+   // De luz de la escena (ya con la exposición) a luz de pantalla en [0, 1]
+   pub fn apply(self, x: f32) -> f32 {
+      let x = x.max(0.0);
+      match self {
+         ToneMapping::Reinhard => x / (1.0 + x),
+         ToneMapping::Aces => (x * (2.51 * x + 0.03) / (x * (2.43 * x + 0.59) + 0.14)).min(1.0),
+      }
+   }
+
+   // Luz de la escena que `apply` lleva a `y`. El blanco se toma un poco por
+   // debajo de 1, porque Reinhard solo lo alcanza en el infinito.
+   pub fn invert(self, y: f32) -> f32 {
+      let y = y.clamp(0.0, 0.9999);
+      match self {
+         ToneMapping::Reinhard => y / (1.0 - y),
+         ToneMapping::Aces => {
+            // Raíz positiva de (2.43y - 2.51)x² + (0.59y - 0.03)x + 0.14y = 0
+            let a = 2.43 * y - 2.51;
+            let b = 0.59 * y - 0.03;
+            let c = 0.14 * y;
+            (-b - (b * b - 4.0 * a * c).sqrt()) / (2.0 * a)
+         },
+      }
+   }
+}
+
+// Cómo se lleva a la pantalla la imagen en punto flotante: se escala por la
+// exposición, se comprime con la curva y después se codifica en sRGB
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMapper {
+   pub tone_mapping: ToneMapping,
+   // En pasos (EV): cada paso duplica la luz
+   pub exposure: f32,
+}
+
+impl Default for ToneMapper {
+   fn default() -> Self {
+      ToneMapper { tone_mapping: ToneMapping::Aces, exposure: 0.0 }
+   }
+}
+
+impl ToneMapper {
+   // Luz de pantalla lineal en [0, 1] para la luz de la escena `radiance`
+   pub fn map(&self, radiance: Vec3) -> Vec3 {
+      let scale = self.exposure.exp2();
+      let curve = |c: f32| self.tone_mapping.apply(c * scale);
+      Vec3::new(curve(radiance.x), curve(radiance.y), curve(radiance.z))
+   }
+
+   // Luz de la escena que se ve como `display` (lineal). Sirve para poner un
+   // fondo ya dibujado detrás de la escena sin que cambie su color.
+   pub fn unmap(&self, display: Vec3) -> Vec3 {
+      let scale = self.exposure.exp2();
+      let curve = |c: f32| self.tone_mapping.invert(c) / scale;
+      Vec3::new(curve(display.x), curve(display.y), curve(display.z))
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use glm::Vec3;
+   use crate::color::Color;
+   use super::{ToneMapper, ToneMapping};
+
+   #[test]
+   fn hdr_round_trips() {
+      // Decodificar y volver a codificar en sRGB da el mismo byte
+      for value in 0..=255 {
+         let color = Color::new(value, value, value);
+         assert_eq!(Color::from_linear(color.to_linear()), color);
+      }
+
+      // Cada curva, con cualquier exposición, devuelve la luz de la que se partió
+      for tone_mapping in [ToneMapping::Reinhard, ToneMapping::Aces] {
+         for exposure in [-2.0, 0.0, 1.5] {
+            let tone_mapper = ToneMapper { tone_mapping, exposure };
+            let display = Vec3::new(0.02, 0.5, 0.95);
+            let back = tone_mapper.map(tone_mapper.unmap(display));
+            assert!(glm::length(back - display) < 1e-4, "{} {}: {:?}", tone_mapping.name(), exposure, back);
+         }
+      }
+   }
+}
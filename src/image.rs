@@ -0,0 +1,276 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::color::Color;
+use crate::png;
+
+// Imagen RGB en memoria, fila por fila desde la esquina superior izquierda
+pub struct Image {
+   pub width: usize,
+   pub height: usize,
+   pub pixels: Vec<Color>,
+}
+
+fn invalid(message: String) -> io::Error {
+   io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl Image {
+   // Cargar una imagen PNG, PPM o TGA según la extensión del archivo
+   pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+      let path = path.as_ref();
+      let data = fs::read(path)?;
+
+      let extension = path.extension()
+         .and_then(|e| e.to_str())
+         .map(|e| e.to_ascii_lowercase())
+         .unwrap_or_default();
+
+      match extension.as_str() {
+         "png" => png::decode(&data),
+         "ppm" => decode_ppm(&data),
+         "tga" => decode_tga(&data),
+         _ => Err(invalid(format!("formato de imagen no soportado: {}", path.display()))),
+      }
+   }
+
+   // Guardar como PNG o PPM (P6) según la extensión del archivo
+   pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+      let path = path.as_ref();
+      let extension = path.extension()
+         .and_then(|e| e.to_str())
+         .map(|e| e.to_ascii_lowercase())
+         .unwrap_or_default();
+
+      let data = match extension.as_str() {
+         "png" => png::encode(self),
+         "ppm" => encode_ppm(self),
+         _ => return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("formato de imagen no soportado: {}", path.display()),
+         )),
+      };
+      fs::write(path, data)
+   }
+
+   pub fn get(&self, x: usize, y: usize) -> Color {
+      self.pixels[y * self.width + x]
+   }
+}
+
+// PPM binario (P6) o en texto (P3)
+fn decode_ppm(data: &[u8]) -> io::Result<Image> {
+   // Leer los cuatro campos del encabezado, saltando comentarios
+   let mut fields = Vec::new();
+   let mut pos = 0;
+   while fields.len() < 4 {
+      while pos < data.len() && data[pos].is_ascii_whitespace() {
+         pos += 1;
+      }
+      if pos < data.len() && data[pos] == b'#' {
+         while pos < data.len() && data[pos] != b'\n' {
+            pos += 1;
+         }
+         continue;
+      }
+      let start = pos;
+      while pos < data.len() && !data[pos].is_ascii_whitespace() {
+         pos += 1;
+      }
+      if start == pos {
+         return Err(invalid("PPM: encabezado incompleto".to_string()));
+      }
+      fields.push(String::from_utf8_lossy(&data[start..pos]).into_owned());
+   }
+
+   let number = |s: &str| s.parse::<usize>().map_err(|_| invalid(format!("PPM: número inválido '{}'", s)));
+   let width = number(&fields[1])?;
+   let height = number(&fields[2])?;
+   let max = number(&fields[3])?;
+   if max == 0 || max > 65535 {
+      return Err(invalid("PPM: valor máximo inválido".to_string()));
+   }
+   if width == 0 || height == 0 {
+      return Err(invalid("PPM: imagen vacía".to_string()));
+   }
+   let scale = |v: usize| (v * 255 / max) as u8;
+
+   let samples: Vec<usize> = match fields[0].as_str() {
+      "P6" => {
+         // Un solo espacio separa el encabezado de los datos binarios
+         let body = &data[(pos + 1).min(data.len())..];
+         if max < 256 {
+            body.iter().map(|&b| b as usize).collect()
+         } else {
+            body.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]]) as usize).collect()
+         }
+      },
+      "P3" => String::from_utf8_lossy(&data[pos..])
+         .split_whitespace()
+         .map(number)
+         .collect::<io::Result<_>>()?,
+      other => return Err(invalid(format!("PPM: formato '{}' no soportado", other))),
+   };
+
+   if samples.len() < width * height * 3 {
+      return Err(invalid("PPM: datos de imagen incompletos".to_string()));
+   }
+   if let Some(v) = samples[..width * height * 3].iter().find(|&&v| v > max) {
+      return Err(invalid(format!("PPM: valor {} mayor que el máximo {}", v, max)));
+   }
+
+   let pixels = samples.chunks_exact(3)
+      .take(width * height)
+      .map(|c| Color::new(scale(c[0]), scale(c[1]), scale(c[2])))
+      .collect();
+
+   Ok(Image { width, height, pixels })
+}
+
+fn encode_ppm(image: &Image) -> Vec<u8> {
+   let mut data = format!("P6\n{} {}\n255\n", image.width, image.height).into_bytes();
+   data.reserve(image.pixels.len() * 3);
+   for pixel in &image.pixels {
+      data.extend_from_slice(&[pixel.r, pixel.g, pixel.b]);
+   }
+   data
+}
+
+// TGA de color verdadero (24/32 bits) o escala de grises (8 bits), con o sin RLE
+fn decode_tga(data: &[u8]) -> io::Result<Image> {
+   if data.len() < 18 {
+      return Err(invalid("TGA: encabezado incompleto".to_string()));
+   }
+
+   let id_length = data[0] as usize;
+   let colormap_type = data[1];
+   let image_type = data[2];
+   let colormap_length = u16::from_le_bytes([data[5], data[6]]) as usize;
+   let colormap_depth = data[7] as usize;
+   let width = u16::from_le_bytes([data[12], data[13]]) as usize;
+   let height = u16::from_le_bytes([data[14], data[15]]) as usize;
+   let depth = data[16] as usize;
+   let top_to_bottom = data[17] & 0x20 != 0;
+   if width == 0 || height == 0 {
+      return Err(invalid("TGA: imagen vacía".to_string()));
+   }
+
+   let (rle, grayscale) = match image_type {
+      2 => (false, false),
+      3 => (false, true),
+      10 => (true, false),
+      11 => (true, true),
+      _ => return Err(invalid(format!("TGA: tipo de imagen {} no soportado", image_type))),
+   };
+   let bytes_per_pixel = depth / 8;
+   if (grayscale && depth != 8) || (!grayscale && depth != 24 && depth != 32) {
+      return Err(invalid(format!("TGA: profundidad de {} bits no soportada", depth)));
+   }
+
+   // Saltar el campo de identificación y la paleta (si la hubiera)
+   let mut pos = 18 + id_length;
+   if colormap_type == 1 {
+      pos += colormap_length * colormap_depth.div_ceil(8);
+   }
+
+   let read_pixel = |p: &[u8]| {
+      if grayscale {
+         Color::new(p[0], p[0], p[0])
+      } else {
+         // Los pixeles vienen en orden BGR(A)
+         Color::new(p[2], p[1], p[0])
+      }
+   };
+
+   let count = width * height;
+   let mut pixels = Vec::with_capacity(count);
+   let truncated = || invalid("TGA: datos de imagen incompletos".to_string());
+
+   while pixels.len() < count {
+      if rle {
+         let header = *data.get(pos).ok_or_else(truncated)?;
+         pos += 1;
+         let run = (header & 0x7f) as usize + 1;
+         if header & 0x80 != 0 {
+            // Paquete repetido: un solo pixel que se repite `run` veces
+            let p = data.get(pos..pos + bytes_per_pixel).ok_or_else(truncated)?;
+            pixels.extend(std::iter::repeat_n(read_pixel(p), run));
+            pos += bytes_per_pixel;
+         } else {
+            // Paquete crudo: `run` pixeles distintos
+            for _ in 0..run {
+               let p = data.get(pos..pos + bytes_per_pixel).ok_or_else(truncated)?;
+               pixels.push(read_pixel(p));
+               pos += bytes_per_pixel;
+            }
+         }
+      } else {
+         let p = data.get(pos..pos + bytes_per_pixel).ok_or_else(truncated)?;
+         pixels.push(read_pixel(p));
+         pos += bytes_per_pixel;
+      }
+   }
+   pixels.truncate(count);
+
+   // Por defecto TGA guarda las filas de abajo hacia arriba
+   if !top_to_bottom {
+      let rows: Vec<Vec<Color>> = pixels.chunks(width).rev().map(|r| r.to_vec()).collect();
+      pixels = rows.concat();
+   }
+
+   Ok(Image { width, height, pixels })
+}
+
+#[cfg(test)]
+mod tests {
+   use crate::color::Color;
+   use super::{decode_ppm, decode_tga};
+
+   // Encabezado TGA de 18 bytes sin campo de identificación ni paleta
+   fn tga_header(image_type: u8, width: u16, height: u16, depth: u8, descriptor: u8) -> Vec<u8> {
+      let mut header = vec![0, 0, image_type, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+      header.extend_from_slice(&width.to_le_bytes());
+      header.extend_from_slice(&height.to_le_bytes());
+      header.extend_from_slice(&[depth, descriptor]);
+      header
+   }
+
+   #[test]
+   fn tga_rle_packets() {
+      // 24 bits, filas de abajo hacia arriba: un paquete repetido de dos
+      // pixeles rojos y uno crudo de cuatro pixeles en BGR
+      let mut data = tga_header(10, 3, 2, 24, 0);
+      data.extend_from_slice(&[0x81, 0, 0, 255]);
+      data.extend_from_slice(&[0x03, 255, 0, 0, 0, 255, 0, 10, 20, 30, 1, 2, 3]);
+      let image = decode_tga(&data).unwrap();
+      let red = Color::new(255, 0, 0);
+      assert_eq!(image.pixels, [
+         Color::new(0, 255, 0), Color::new(30, 20, 10), Color::new(3, 2, 1),
+         red, red, Color::new(0, 0, 255),
+      ]);
+
+      // Escala de grises de arriba hacia abajo; el último paquete se pasa del
+      // final de la imagen y lo que sobra se descarta
+      let mut data = tga_header(11, 2, 2, 8, 0x20);
+      data.extend_from_slice(&[0x00, 7, 0x83, 200]);
+      let image = decode_tga(&data).unwrap();
+      let gray = |v: u8| Color::new(v, v, v);
+      assert_eq!(image.pixels, [gray(7), gray(200), gray(200), gray(200)]);
+
+      // Un paquete que promete más pixeles de los que trae
+      let mut data = tga_header(10, 2, 1, 24, 0);
+      data.extend_from_slice(&[0x01, 1, 2, 3]);
+      assert!(decode_tga(&data).is_err());
+   }
+
+   #[test]
+   fn ppm_text_format() {
+      let image = decode_ppm(b"P3\n# comentario\n2 1\n15\n15 0 0   0 15 7\n").unwrap();
+      assert_eq!((image.width, image.height), (2, 1));
+      assert_eq!(image.pixels, [Color::new(255, 0, 0), Color::new(0, 255, 119)]);
+
+      assert!(decode_ppm(b"P3 1 1 15 16 0 0").is_err(), "valor mayor que el máximo");
+      assert!(decode_ppm(b"P3 2 1 255 1 2 3").is_err(), "datos incompletos");
+      assert!(decode_ppm(b"P3 1 1 255 1 dos 3").is_err(), "número inválido");
+   }
+}
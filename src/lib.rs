@@ -0,0 +1,35 @@
+// Renderizador 3D por software: carga modelos .obj con sus materiales y los
+// dibuja en un Framebuffer. La ventana de SDL y la línea de comandos viven en
+// el programa (main.rs, options.rs, y con la opción `sdl` window.rs y
+// presenter.rs, que muestra el Framebuffer en la ventana); la biblioteca no
+// depende de SDL y se puede usar desde otras herramientas.
+
+pub mod camera;
+pub mod clipping;
+pub mod clock;
+pub mod color;
+pub mod font;
+pub mod framebuffer;
+pub mod hdr;
+pub mod image;
+pub mod light;
+pub mod line;
+pub mod material;
+pub mod msaa;
+pub mod obj_loader;
+pub mod orbit;
+pub mod png;
+pub mod postprocess;
+pub mod renderer;
+pub mod shader;
+pub mod shading;
+pub mod texture;
+pub mod tiles;
+pub mod transform;
+pub mod triangle;
+pub mod wireframe;
+
+pub use framebuffer::Framebuffer;
+pub use obj_loader::Model;
+pub use renderer::Renderer;
+pub use transform::Transform;
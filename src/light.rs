@@ -0,0 +1,208 @@
+use glm::Vec3;
+
+// Atenuación por distancia: 1 / (constante + lineal * d + cuadrática * d²)
+#[derive(Debug, Clone, Copy)]
+pub struct Attenuation {
+   pub constant: f32,
+   pub linear: f32,
+   pub quadratic: f32,
+}
+
+impl Attenuation {
+   pub fn new(constant: f32, linear: f32, quadratic: f32) -> Self {
+      Attenuation { constant, linear, quadratic }
+   }
+
+   // Sin atenuación (la luz no pierde intensidad con la distancia)
+   pub fn none() -> Self {
+      Attenuation::new(1.0, 0.0, 0.0)
+   }
+
+   fn factor(&self, distance: f32) -> f32 {
+      1.0 / (self.constant + self.linear * distance + self.quadratic * distance * distance)
+   }
+}
+
+// Tipos de luz soportados
+#[derive(Debug, Clone, Copy)]
+pub enum LightKind {
+   // Luz muy lejana (como el sol): solo importa su dirección
+   Directional { direction: Vec3 },
+   // Luz que emite en todas direcciones desde un punto
+   Point { position: Vec3, attenuation: Attenuation },
+   // Luz puntual limitada a un cono. Los ángulos (en radianes) marcan
+   // dónde empieza a caer la intensidad y dónde llega a cero.
+   Spot {
+      position: Vec3,
+      direction: Vec3,
+      attenuation: Attenuation,
+      inner_angle: f32,
+      outer_angle: f32,
+   },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Light {
+   pub kind: LightKind,
+   pub color: Vec3,
+   pub intensity: f32,
+}
+
+impl Light {
+   pub fn directional(direction: Vec3, color: Vec3, intensity: f32) -> Self {
+      Light {
+         kind: LightKind::Directional { direction: glm::normalize(direction) },
+         color,
+         intensity,
+      }
+   }
+
+   pub fn point(position: Vec3, color: Vec3, intensity: f32, attenuation: Attenuation) -> Self {
+      Light {
+         kind: LightKind::Point { position, attenuation },
+         color,
+         intensity,
+      }
+   }
+
+   pub fn spot(
+      position: Vec3,
+      direction: Vec3,
+      color: Vec3,
+      intensity: f32,
+      attenuation: Attenuation,
+      inner_angle: f32,
+      outer_angle: f32,
+   ) -> Self {
+      Light {
+         kind: LightKind::Spot {
+            position,
+            direction: glm::normalize(direction),
+            attenuation,
+            inner_angle,
+            outer_angle,
+         },
+         color,
+         intensity,
+      }
+   }
+
+   // Dirección hacia la luz (normalizada) y energía que llega al punto. Una
+   // luz puntual o un foco justo en el punto no tiene dirección y no aporta.
+   fn incident(&self, point: Vec3) -> (Vec3, Vec3) {
+      let radiance = self.color * self.intensity;
+      let none = (Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0));
+      match self.kind {
+         LightKind::Directional { direction } => (-direction, radiance),
+         LightKind::Point { position, attenuation } => {
+            let to_light = position - point;
+            let distance = glm::length(to_light);
+            if distance <= f32::EPSILON {
+               return none;
+            }
+            (to_light / distance, radiance * attenuation.factor(distance))
+         },
+         LightKind::Spot { position, direction, attenuation, inner_angle, outer_angle } => {
+            let to_light = position - point;
+            let distance = glm::length(to_light);
+            if distance <= f32::EPSILON {
+               return none;
+            }
+            let l = to_light / distance;
+
+            // Caída suave entre el cono interior y el exterior
+            let cos_theta = glm::dot(-l, direction);
+            let cone = glm::smoothstep(outer_angle.cos(), inner_angle.cos(), cos_theta);
+
+            (l, radiance * (attenuation.factor(distance) * cone))
+         },
+      }
+   }
+}
+
+// Propiedades de la superficie que usa el modelo de iluminación
+#[derive(Debug, Clone, Copy)]
+pub struct Surface {
+   pub albedo: Vec3,
+   pub ambient: Vec3,   // Cuánto de la luz ambiental refleja (multiplica al albedo)
+   pub specular: Vec3,
+   pub shininess: f32,
+   // Luz propia, que se suma sin importar las luces (por ejemplo el brillo de
+   // los propulsores)
+   pub emission: Vec3,
+}
+
+// Conjunto de luces de la escena más una luz ambiental
+pub struct Lighting {
+   pub ambient: Vec3,
+   pub lights: Vec<Light>,
+}
+
+impl Lighting {
+   pub fn new(ambient: Vec3) -> Self {
+      Lighting { ambient, lights: Vec::new() }
+   }
+
+   pub fn add(&mut self, light: Light) {
+      self.lights.push(light);
+   }
+
+   // Calcular el color de un punto con Lambert (difusa) + Blinn-Phong (especular).
+   // Todas las posiciones y la normal deben estar en espacio de mundo.
+   pub fn shade(&self, surface: &Surface, position: Vec3, normal: Vec3, eye: Vec3) -> Vec3 {
+      let n = glm::normalize(normal);
+      let v = glm::normalize(eye - position);
+
+      let mut color = surface.emission + self.ambient * surface.ambient * surface.albedo;
+
+      for light in &self.lights {
+         let (l, radiance) = light.incident(position);
+
+         let n_dot_l = glm::dot(n, l);
+         if n_dot_l <= 0.0 {
+            continue;
+         }
+
+         let diffuse = surface.albedo * n_dot_l;
+
+         let h = glm::normalize(l + v);
+         let n_dot_h = glm::dot(n, h).max(0.0);
+         let specular = surface.specular * n_dot_h.powf(surface.shininess);
+
+         color = color + (diffuse + specular) * radiance;
+      }
+
+      color
+   }
+}
+
+// Luces de la escena: una luz principal direccional, un relleno puntual
+// azulado y un foco que ilumina la nave desde arriba
+pub fn default_lighting() -> Lighting {
+   let mut lighting = Lighting::new(Vec3::new(0.12, 0.12, 0.15));
+
+   lighting.add(Light::directional(
+      Vec3::new(1.0, -1.0, -1.0),
+      Vec3::new(1.0, 0.96, 0.9),
+      0.7,
+   ));
+
+   lighting.add(Light::point(
+      Vec3::new(2.5, -1.0, 2.0),
+      Vec3::new(0.55, 0.7, 1.0),
+      0.5,
+      Attenuation::new(1.0, 0.09, 0.032),
+   ));
+
+   lighting.add(Light::spot(
+      Vec3::new(0.0, 3.0, 3.0),
+      Vec3::new(0.0, -1.0, -1.0),
+      Vec3::new(1.0, 1.0, 1.0),
+      0.4,
+      Attenuation::none(),
+      20.0_f32.to_radians(),
+      35.0_f32.to_radians(),
+   ));
+
+   lighting
+}
@@ -0,0 +1,238 @@
+use glm::Vec3;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+
+// Forma de los extremos de una línea
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineCap {
+   // Termina justo en el extremo
+   Butt,
+   // Se prolonga medio ancho más allá del extremo
+   Square,
+   // Semicírculo del mismo ancho que la línea
+   Round,
+}
+
+impl LineCap {
+   pub fn name(self) -> &'static str {
+      match self {
+         LineCap::Butt => "butt",
+         LineCap::Square => "square",
+         LineCap::Round => "round",
+      }
+   }
+}
+
+// Cómo se ve una línea
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineStyle {
+   pub color: Color,
+   // Ancho en pixeles, puede ser fraccionario
+   pub width: f32,
+   pub cap: LineCap,
+   // Suavizar los bordes mezclando según la parte del pixel que cubre la
+   // línea; sin suavizado se pintan pixeles enteros
+   pub antialiased: bool,
+}
+
+impl LineStyle {
+   // Hasta cuántos pixeles del eje de la línea puede llegar a pintar
+   pub fn reach(&self) -> f32 {
+      if self.antialiased {
+         self.width * 0.5 + 1.0
+      } else {
+         (self.width.round().max(1.0) * 0.5).ceil()
+      }
+   }
+}
+
+// Dibujar una línea entre dos puntos de pantalla con x, y en pixeles (con
+// decimales: los centros de los pixeles están en +0.5) y z como profundidad.
+// Con `depth_offset` cada pixel se compara contra el z-buffer con la línea
+// adelantada esa cantidad hacia la cámara; la línea no escribe profundidad.
+pub fn draw_line(framebuffer: &mut Framebuffer, a: Vec3, b: Vec3, style: &LineStyle, depth_offset: Option<f32>) {
+   if !style.antialiased {
+      draw_aliased(framebuffer, a, b, style, depth_offset);
+   } else if style.width <= 1.0 {
+      draw_thin(framebuffer, a, b, style, depth_offset);
+   } else {
+      draw_thick(framebuffer, a, b, style, depth_offset);
+   }
+}
+
+// Pintar un pixel con la cobertura dada (0 = nada, 1 = el color de la línea)
+// si pasa la prueba de profundidad
+fn plot(framebuffer: &mut Framebuffer, x: i32, y: i32, z: f32, coverage: f32, color: Color, depth_offset: Option<f32>) {
+   if coverage <= 0.0 || x < 0 || y < 0 || x >= framebuffer.width as i32 || y >= framebuffer.height as i32 {
+      return;
+   }
+   let index = y as usize * framebuffer.width + x as usize;
+   if depth_offset.is_some_and(|offset| z - offset > framebuffer.z_buffer[index]) {
+      return;
+   }
+   let pixel = &mut framebuffer.buffer[index];
+   *pixel = if coverage >= 1.0 { color } else { pixel.blend(color, coverage) };
+}
+
+// Sin suavizado: un pincel cuadrado del ancho redondeado, paso a paso sobre
+// el eje más largo. El pincel ya deja los extremos cuadrados.
+fn draw_aliased(framebuffer: &mut Framebuffer, a: Vec3, b: Vec3, style: &LineStyle, depth_offset: Option<f32>) {
+   let steps = (b.x - a.x).abs().max((b.y - a.y).abs()).round().max(1.0) as usize;
+   let thickness = style.width.round().max(1.0) as i32;
+   let low = -(thickness - 1) / 2;
+   let high = thickness / 2;
+
+   for i in 0..=steps {
+      let t = i as f32 / steps as f32;
+      // Pixel que contiene el punto
+      let x = (a.x + (b.x - a.x) * t).floor() as i32;
+      let y = (a.y + (b.y - a.y) * t).floor() as i32;
+      let z = a.z + (b.z - a.z) * t;
+
+      for py in y + low..=y + high {
+         for px in x + low..=x + high {
+            plot(framebuffer, px, py, z, 1.0, style.color, depth_offset);
+         }
+      }
+   }
+}
+
+// Líneas de hasta un pixel de ancho con el algoritmo de Xiaolin Wu: en cada
+// columna (o fila, si la línea es empinada) se reparte el color entre los dos
+// pixeles más cercanos según la distancia a cada centro, y los extremos se
+// pintan en proporción a la parte del pixel que alcanzan
+fn draw_thin(framebuffer: &mut Framebuffer, a: Vec3, b: Vec3, style: &LineStyle, depth_offset: Option<f32>) {
+   let (a, b) = extend(a, b, style.cap, style.width * 0.5);
+   // Coordenadas con los centros de los pixeles en enteros
+   let (mut x0, mut y0, mut x1, mut y1) = (a.x - 0.5, a.y - 0.5, b.x - 0.5, b.y - 0.5);
+   let (mut z0, mut z1) = (a.z, b.z);
+   let steep = (y1 - y0).abs() > (x1 - x0).abs();
+   if steep {
+      std::mem::swap(&mut x0, &mut y0);
+      std::mem::swap(&mut x1, &mut y1);
+   }
+   if x0 > x1 {
+      std::mem::swap(&mut x0, &mut x1);
+      std::mem::swap(&mut y0, &mut y1);
+      std::mem::swap(&mut z0, &mut z1);
+   }
+
+   let dx = x1 - x0;
+   if dx < 1e-6 {
+      return;
+   }
+   let gradient = (y1 - y0) / dx;
+   let depth = |x: f32| z0 + (z1 - z0) * ((x - x0) / dx).clamp(0.0, 1.0);
+   let mut put = |x: i32, y: i32, z: f32, coverage: f32| {
+      let (px, py) = if steep { (y, x) } else { (x, y) };
+      plot(framebuffer, px, py, z, coverage * style.width, style.color, depth_offset);
+   };
+   let fract = |v: f32| v - v.floor();
+
+   // Extremos: la columna donde cae cada uno se pinta solo en la parte que
+   // cubre la línea
+   let start = (x0 + 0.5).floor();
+   let end = (x1 + 0.5).floor();
+   let start_y = y0 + gradient * (start - x0);
+   let end_y = y1 + gradient * (end - x1);
+   if start == end {
+      // La línea entera cae en una columna
+      let y = (start_y + end_y) * 0.5;
+      put(start as i32, y.floor() as i32, depth(start), (1.0 - fract(y)) * dx);
+      put(start as i32, y.floor() as i32 + 1, depth(start), fract(y) * dx);
+      return;
+   }
+   let start_gap = 1.0 - fract(x0 + 0.5);
+   let end_gap = fract(x1 + 0.5);
+   put(start as i32, start_y.floor() as i32, depth(start), (1.0 - fract(start_y)) * start_gap);
+   put(start as i32, start_y.floor() as i32 + 1, depth(start), fract(start_y) * start_gap);
+   put(end as i32, end_y.floor() as i32, depth(end), (1.0 - fract(end_y)) * end_gap);
+   put(end as i32, end_y.floor() as i32 + 1, depth(end), fract(end_y) * end_gap);
+
+   // Columnas intermedias
+   let mut y = start_y + gradient;
+   for x in start as i32 + 1..end as i32 {
+      let z = depth(x as f32);
+      put(x, y.floor() as i32, z, 1.0 - fract(y));
+      put(x, y.floor() as i32 + 1, z, fract(y));
+      y += gradient;
+   }
+}
+
+// Líneas gruesas: la cobertura de cada pixel sale de su distancia con signo
+// al borde de la línea (un rectángulo, o una cápsula con extremos redondos),
+// que con un filtro de un pixel de ancho es 0.5 - distancia
+fn draw_thick(framebuffer: &mut Framebuffer, a: Vec3, b: Vec3, style: &LineStyle, depth_offset: Option<f32>) {
+   let radius = style.width * 0.5;
+   let (dx, dy) = (b.x - a.x, b.y - a.y);
+   let length = (dx * dx + dy * dy).sqrt();
+   if length < 1e-6 && style.cap == LineCap::Butt {
+      return;
+   }
+   // Dirección de la línea; si mide cero (solo queda el extremo) da igual
+   let (ux, uy) = if length < 1e-6 { (1.0, 0.0) } else { (dx / length, dy / length) };
+
+   let distance = |px: f32, py: f32| -> (f32, f32) {
+      // Coordenadas del punto a lo largo de la línea y a través de ella
+      let along = (px - a.x) * ux + (py - a.y) * uy;
+      let across = -(px - a.x) * uy + (py - a.y) * ux;
+      let t = if length < 1e-6 { 0.0 } else { (along / length).clamp(0.0, 1.0) };
+      let d = match style.cap {
+         LineCap::Round => {
+            let nearest = along.clamp(0.0, length);
+            ((along - nearest).powi(2) + across * across).sqrt() - radius
+         },
+         LineCap::Butt | LineCap::Square => {
+            let extra = if style.cap == LineCap::Square { radius } else { 0.0 };
+            let half = length * 0.5 + extra;
+            let qx = (along - length * 0.5).abs() - half;
+            let qy = across.abs() - radius;
+            (qx.max(0.0).powi(2) + qy.max(0.0).powi(2)).sqrt() + qx.max(qy).min(0.0)
+         },
+      };
+      (d, t)
+   };
+
+   // Recorrer fila por fila solo el tramo que puede tocar la línea: los
+   // puntos del eje (con sus extremos prolongados) a menos de `reach` de la
+   // fila, ensanchados `reach` a cada lado
+   let reach = radius + 1.0;
+   let (ea, eb) = extend(a, b, if style.cap == LineCap::Butt { LineCap::Butt } else { LineCap::Square }, radius);
+   let y_min = (ea.y.min(eb.y) - reach).floor() as i32;
+   let y_max = (ea.y.max(eb.y) + reach).ceil() as i32;
+   for y in y_min.max(0)..=y_max.min(framebuffer.height as i32 - 1) {
+      let center_y = y as f32 + 0.5;
+      let (t0, t1) = if (eb.y - ea.y).abs() < 1e-6 {
+         (0.0, 1.0)
+      } else {
+         let t0 = (center_y - reach - ea.y) / (eb.y - ea.y);
+         let t1 = (center_y + reach - ea.y) / (eb.y - ea.y);
+         (t0.min(t1).max(0.0), t0.max(t1).min(1.0))
+      };
+      if t0 > t1 {
+         continue;
+      }
+      let xa = ea.x + (eb.x - ea.x) * t0;
+      let xb = ea.x + (eb.x - ea.x) * t1;
+      let x_min = (xa.min(xb) - reach).floor() as i32;
+      let x_max = (xa.max(xb) + reach).ceil() as i32;
+
+      for x in x_min.max(0)..=x_max.min(framebuffer.width as i32 - 1) {
+         let (d, t) = distance(x as f32 + 0.5, center_y);
+         let z = a.z + (b.z - a.z) * t;
+         plot(framebuffer, x, y, z, (0.5 - d).min(1.0), style.color, depth_offset);
+      }
+   }
+}
+
+// Prolongar los dos extremos `amount` pixeles en la dirección de la línea si
+// el extremo es cuadrado o redondo
+fn extend(a: Vec3, b: Vec3, cap: LineCap, amount: f32) -> (Vec3, Vec3) {
+   let (dx, dy) = (b.x - a.x, b.y - a.y);
+   let length = (dx * dx + dy * dy).sqrt();
+   if cap == LineCap::Butt || length < 1e-6 {
+      return (a, b);
+   }
+   let offset = Vec3::new(dx / length * amount, dy / length * amount, 0.0);
+   (a - offset, b + offset)
+}
@@ -1,425 +1,268 @@
-extern crate sdl2;
 extern crate glm;
 
-mod color;
-mod framebuffer;
-mod triangle;
-mod obj_loader;
+mod options;
+#[cfg(feature = "sdl")]
+mod presenter;
+#[cfg(feature = "sdl")]
+mod window;
 
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
 use std::path::Path;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use color::Color;
-use framebuffer::{Framebuffer, SCREEN_WIDTH, SCREEN_HEIGHT};
-use triangle::draw_triangle;
-use obj_loader::Model;
+use space_ship_render::camera::Camera;
+use space_ship_render::color::Color;
+use space_ship_render::framebuffer::{Framebuffer, SCREEN_WIDTH, SCREEN_HEIGHT};
+use space_ship_render::light::default_lighting;
+use space_ship_render::obj_loader::{Model, Object, SMOOTHING_ANGLE};
+use space_ship_render::orbit::OrbitController;
+use space_ship_render::postprocess::{Lut, PostChain};
+use space_ship_render::renderer::{Part, RenderStats, Renderer};
+use space_ship_render::transform::Transform;
+use space_ship_render::shader::{FlashShader, FragmentShader, HologramShader, NormalShader, StandardFragmentShader, ToonShader};
+use space_ship_render::texture::Texture;
 use glm::Vec3;
+use options::{Options, USAGE};
+
+// Objetos de la nave exportada desde Blender que son los propulsores. Se
+// pintan de gris azulado y brillan con luz propia, que pasa de 1: sin HDR se
+// recorta y con HDR la curva de tono la comprime.
+const ENGINE_OBJECTS: [&str; 2] = ["Circle", "Circle.001"];
+const ENGINE_COLOR: Color = Color { r: 120, g: 140, b: 160 };
+const ENGINE_GLOW: Vec3 = Vec3 { x: 3.0, y: 0.9, z: 0.2 };
+
+// Efectos de pixel que se recorren con la tecla V. Sin la ventana solo se
+// usa el estándar, igual que el resto del estado que manejan las teclas.
+#[derive(Clone, Copy)]
+#[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+enum Effect {
+    Standard,
+    Toon,
+    Normals,
+    Hologram,
+}
+
+impl Effect {
+    fn shader(self) -> Box<dyn FragmentShader> {
+        match self {
+            Effect::Standard => Box::new(StandardFragmentShader),
+            Effect::Toon => Box::new(ToonShader { bands: 4 }),
+            Effect::Normals => Box::new(NormalShader),
+            Effect::Hologram => Box::new(HologramShader { color: Vec3::new(0.3, 0.9, 1.0) }),
+        }
+    }
+}
+
+// Velocidad actual de la nave, que sigue a las teclas mantenidas
+#[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+struct Motion {
+    // Giro alrededor de los ejes X, Y y Z de la pantalla y desplazamiento a
+    // lo largo de ellos
+    angular: Vec3,
+    linear: Vec3,
+}
 
 // Estructura para mantener el estado de la aplicación
+#[cfg_attr(not(feature = "sdl"), allow(dead_code))]
 struct AppState {
     framebuffer: Framebuffer,
     current_color: Color,
     model: Model,
-    center: Vec3,
-    scale_factor: f32,
-    angle_x: f32,
-    angle_y: f32,
-    offset_x: f32,
-    offset_y: f32,
+    renderer: Renderer,
+    // Post-proceso y el efecto elegido con la tecla P
+    post: PostChain,
+    selected_pass: usize,
+    transform: Transform,
+    // Transformación con los ángulos de la línea de comandos, a la que vuelve R
+    initial_transform: Transform,
+    // Objeto elegido con Tab, o uno de sus grupos
+    selected_part: (usize, Option<usize>),
+    // Cámara que se mueve con el mouse
+    orbit: OrbitController,
+    motion: Motion,
+    effect: Effect,
+    // Intensidad del destello de daño: 1 al activarlo y baja hasta 0
+    flash: f32,
+    // Panel con estadísticas del cuadro (tecla I)
+    show_hud: bool,
+    stats: RenderStats,
+    // Tiempo que tomó dibujar el último cuadro
+    render_time: Duration,
 }
 
-fn init() -> Result<(sdl2::Sdl, sdl2::render::Canvas<sdl2::video::Window>, AppState), String> {
-    let sdl_context = sdl2::init()?;
-    let video_subsystem = sdl_context.video()?;
-    
-    let window = video_subsystem.window("Software Renderer", SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32)
-        .position_centered()
-        .build()
-        .map_err(|e| e.to_string())?;
-    
-    let canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
-    
+// Cargar el modelo y preparar la escena; no necesita SDL, así que sirve tanto
+// para la ventana como para renderizar a un archivo
+fn load_app_state(options: &Options) -> Result<AppState, String> {
     // Cargar el modelo .obj
-    let model = Model::load_obj(Path::new("models/NavePrototipo2.obj"))
+    let mut model = Model::load_obj(&options.model)
         .map_err(|e| e.to_string())?;
+    for warning in &model.warnings {
+        eprintln!("Aviso: {}", warning);
+    }
+    
+    // Si el archivo pide sombreado plano (s 0 u off, como exporta Blender
+    // por defecto) sus normales son una por cara: se recalculan suavizadas
+    // para que las superficies curvas no se vean facetadas, respetando las
+    // aristas duras. Las normales de los demás modelos se dejan como vienen.
+    if model.flat_shading {
+        model.compute_smooth_normals(SMOOTHING_ANGLE);
+    }
     
     println!("Modelo cargado con éxito:");
     println!("  - Vértices: {}", model.vertices.len());
     println!("  - Caras: {}", model.faces.len());
+    println!("  - Materiales: {}", model.materials.len());
+    println!("  - Objetos:");
+    for object in &model.objects {
+        println!("      {} ({} caras)", object.name, object.faces().len());
+        for group in object.groups.iter().filter(|g| !g.name.is_empty()) {
+            println!("        grupo {} ({} caras)", group.name, group.faces.len());
+        }
+    }
     
-    // Calcular el centro y la escala
-    let center = model.calculate_center();
-    let size = model.calculate_size();
-    let scale_factor = (SCREEN_WIDTH.min(SCREEN_HEIGHT) as f32 * 0.8) / size.x.max(size.y).max(size.z);
+    // Cada objeto y cada uno de sus grupos se puede ocultar o pintar por
+    // separado
+    let parts = model.objects.iter().map(initial_part).collect();
     
-    // Crear el estado de la aplicación
-    let app_state = AppState {
-        framebuffer: Framebuffer::new(),
-        current_color: Color::new(255, 255, 0),
-        model,
-        center,
-        scale_factor,
-        angle_x: std::f32::consts::PI / 2.0, // Rotación inicial en X (90 grados)
-        angle_y: std::f32::consts::PI,       // Rotación inicial en Y (180 grados)
-        offset_x: 0.0,
-        offset_y: 0.0,
+    // Textura opcional con la pintura del casco: una imagen junto al modelo con
+    // el mismo nombre (PNG, PPM o TGA). Se usa en las caras cuyo material no
+    // trae su propia textura (map_Kd); si no existe se usan los colores base.
+    let texture_path = ["png", "ppm", "tga"].iter()
+        .map(|extension| options.model.with_extension(extension))
+        .find(|path| path.exists());
+    let texture = if let Some(path) = texture_path {
+        let texture = Texture::load(&path).map_err(|e| e.to_string())?;
+        println!("  - Textura: {} ({}x{})", path.display(), texture.image.width, texture.image.height);
+        Some(texture)
+    } else {
+        None
     };
     
-    Ok((sdl_context, canvas, app_state))
-}
-
-fn clear(app_state: &mut AppState) {
-    app_state.framebuffer.clear(Color::new(0, 0, 0));
-}
-
-fn set_color(app_state: &mut AppState, color: Color) {
-    app_state.current_color = color;
-}
-
-fn render_buffer(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>, app_state: &Framebuffer) {
-    app_state.render(canvas);
-}
-
-fn render(app_state: &mut AppState) {
-    let model = &app_state.model;
-    let center = app_state.center;
-    let scale_factor = app_state.scale_factor;
-    let angle_x = app_state.angle_x;
-    let angle_y = app_state.angle_y;
-    let offset_x = app_state.offset_x;
-    let offset_y = app_state.offset_y;
-    
-    // Limpiar el z-buffer con valores grandes
-    for z in &mut app_state.framebuffer.z_buffer {
-        *z = f32::MAX; // Usar MAX en lugar de INFINITY para evitar problemas numéricos
-    }
-    
-    // Crear una lista de caras para ordenamiento
-    let mut faces_to_render = Vec::new();
+    // Centrar el modelo y escalarlo para que mida 2 unidades
+    let transform = Transform::fit(&model, options.angle_x, options.angle_y);
     
-    // Paleta de colores armoniosa (tonos grises y azules que combinan entre sí)
-    let color_cuerpo = Color::new(200, 210, 220);       // Gris azulado claro
-    let color_propulsores = Color::new(120, 140, 160);  // Gris azulado medio
-    let color_compartimentos = Color::new(80, 100, 130); // Gris azulado oscuro
-    let color_cabina = Color::new(150, 170, 200);       // Azul grisáceo
-    
-    // Procesar todas las caras
-    for (i, face) in model.faces.iter().enumerate() {
-        // Obtener los tres vértices de la cara
-        let v0 = &model.vertices[face[0]];
-        let v1 = &model.vertices[face[1]];
-        let v2 = &model.vertices[face[2]];
-
-        // Rotación FIJA para determinar colores (no cambia con WASD)
-        let fixed_angle_x = std::f32::consts::PI / 2.0; // 90 grados
-        let fixed_angle_y = std::f32::consts::PI;       // 180 grados
-
-        let rotate_fixed_x = |v: &Vec3| -> Vec3 {
-            let y = v.y * fixed_angle_x.cos() - v.z * fixed_angle_x.sin();
-            let z = v.y * fixed_angle_x.sin() + v.z * fixed_angle_x.cos();
-            Vec3::new(v.x, y, z)
-        };
-
-        let rotate_fixed_y = |v: &Vec3| -> Vec3 {
-            let x = v.x * fixed_angle_y.cos() + v.z * fixed_angle_y.sin();
-            let z = -v.x * fixed_angle_y.sin() + v.z * fixed_angle_y.cos();
-            Vec3::new(x, v.y, z)
-        };
-
-        // Aplicar rotación DINÁMICA para visualización (con angle_x, angle_y del usuario)
-        let rotate_x = |v: &Vec3| -> Vec3 {
-            let y = v.y * angle_x.cos() - v.z * angle_x.sin();
-            let z = v.y * angle_x.sin() + v.z * angle_x.cos();
-            Vec3::new(v.x, y, z)
-        };
-
-        let rotate_y = |v: &Vec3| -> Vec3 {
-            let x = v.x * angle_y.cos() + v.z * angle_y.sin();
-            let z = -v.x * angle_y.sin() + v.z * angle_y.cos();
-            Vec3::new(x, v.y, z)
-        };
-
-        // Rotar con rotación FIJA para calcular colores (no cambia)
-        let fv0 = rotate_fixed_y(&rotate_fixed_x(v0));
-        let fv1 = rotate_fixed_y(&rotate_fixed_x(v1));
-        let fv2 = rotate_fixed_y(&rotate_fixed_x(v2));
-
-        // Rotar con rotación DINÁMICA para visualización (cambia con WASD)
-        let rv0 = rotate_y(&rotate_x(v0));
-        let rv1 = rotate_y(&rotate_x(v1));
-        let rv2 = rotate_y(&rotate_x(v2));
-
-        // Calcular la normal de la cara para determinar visibilidad
-        let edge1 = Vec3::new(rv1.x - rv0.x, rv1.y - rv0.y, rv1.z - rv0.z);
-        let edge2 = Vec3::new(rv2.x - rv0.x, rv2.y - rv0.y, rv2.z - rv0.z);
-        let normal = Vec3::new(
-            edge1.y * edge2.z - edge1.z * edge2.y,
-            edge1.z * edge2.x - edge1.x * edge2.z,
-            edge1.x * edge2.y - edge1.y * edge2.x
-        );
-
-        // Solo procesar caras que miran hacia la cámara (backface culling)
-        if normal.z <= 0.0 {
-            continue;
-        }
-
-        // Calcular promedios usando las coordenadas con rotación FIJA
-        // Esto asegura que los colores NO cambien cuando mueves la nave
-        let fixed_avg_x = (fv0.x + fv1.x + fv2.x) / 3.0;
-        let fixed_avg_y = (fv0.y + fv1.y + fv2.y) / 3.0;
-        let fixed_avg_z = (fv0.z + fv1.z + fv2.z) / 3.0;
-
-        // Asignar colores basados en la posición con rotación fija
-        let final_color =
-            // Cabina (parte superior central, Y alto y centro en X)
-            if fixed_avg_y > 0.35 && abs(fixed_avg_x) < 0.25 {
-                color_cabina
-            }
-            // Compartimentos laterales (lados extremos, más restrictivo)
-            else if abs(fixed_avg_x) > 0.7 && fixed_avg_y > -0.1 {
-                color_compartimentos
-            }
-            // Propulsores (parte trasera inferior, Z negativo y Y bajo)
-            else if fixed_avg_z < -0.7 || (fixed_avg_y < -0.2 && abs(fixed_avg_x) < 0.6) {
-                color_propulsores
-            }
-            // Cuerpo principal (todo lo demás)
-            else {
-                color_cuerpo
-            };
-        
-        // Transformar a coordenadas de pantalla
-        let tv0 = Vec3::new(
-            (rv0.x - center.x) * scale_factor + SCREEN_WIDTH as f32 * 0.5 + offset_x,
-            (rv0.y - center.y) * scale_factor + SCREEN_HEIGHT as f32 * 0.5 + offset_y,
-            rv0.z
-        );
-        
-        let tv1 = Vec3::new(
-            (rv1.x - center.x) * scale_factor + SCREEN_WIDTH as f32 * 0.5 + offset_x,
-            (rv1.y - center.y) * scale_factor + SCREEN_HEIGHT as f32 * 0.5 + offset_y,
-            rv1.z
-        );
-        
-        let tv2 = Vec3::new(
-            (rv2.x - center.x) * scale_factor + SCREEN_WIDTH as f32 * 0.5 + offset_x,
-            (rv2.y - center.y) * scale_factor + SCREEN_HEIGHT as f32 * 0.5 + offset_y,
-            rv2.z
-        );
-        
-        // Calcular la profundidad promedio para ordenamiento
-        let avg_z = (rv0.z + rv1.z + rv2.z) / 3.0;
-        
-        // Almacenar solo caras visibles para renderizado
-        faces_to_render.push((avg_z, tv0, tv1, tv2, final_color));
-    }
+    // Cámara en +Z mirando al origen, con Y hacia arriba
+    let mut camera = Camera::new(
+        Vec3::new(0.0, 0.0, 3.0),
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        45.0_f32.to_radians(),
+        options.width as f32 / options.height as f32,
+    );
+    frame_camera(&mut camera, options.width, options.height);
     
-    // Ordenar las caras de atrás hacia adelante (mayor Z a menor Z)
-    faces_to_render.sort_by(|a, b| {
-        b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal)
-    });
+    let orbit = OrbitController::new(&camera);
+    let mut renderer = Renderer::new(camera, default_lighting());
+    renderer.texture = texture;
+    renderer.parts = parts;
+    renderer.threads = options.threads;
+    renderer.render_mode = options.render_mode;
+    renderer.line_style = options.line_style;
+    renderer.msaa = options.msaa;
+    renderer.hdr = options.hdr;
+    renderer.tone_mapper = options.tone_mapper;
     
-    // Dibujar las caras en orden
-    for (_, tv0, tv1, tv2, color) in faces_to_render {
-        draw_triangle(&mut app_state.framebuffer, &tv0, &tv1, &tv2, color);
+    // Efectos sobre la imagen terminada, con la tabla de color pedida
+    let mut post = options.post.clone();
+    post.threads = options.threads;
+    if let Some(path) = &options.lut {
+        let lut = Lut::load_cube(path).map_err(|e| format!("no se pudo cargar {}: {}", path.display(), e))?;
+        post.set_lut(lut);
     }
     
-    // Dibujar contornos después de todas las caras
-    draw_wireframe(app_state);
+    // Crear el estado de la aplicación
+    Ok(AppState {
+        framebuffer: Framebuffer::new(options.width, options.height),
+        current_color: Color::new(255, 255, 0),
+        model,
+        renderer,
+        post,
+        selected_pass: 0,
+        transform,
+        initial_transform: transform,
+        selected_part: (0, None),
+        orbit,
+        motion: Motion { angular: Vec3::new(0.0, 0.0, 0.0), linear: Vec3::new(0.0, 0.0, 0.0) },
+        effect: Effect::Standard,
+        flash: 0.0,
+        show_hud: true,
+        stats: RenderStats::default(),
+        render_time: Duration::ZERO,
+    })
+}
+
+// Encuadrar la nave en un área de `width` x `height`: con 45 grados de campo
+// vertical mientras el área sea al menos tan ancha como 800x600, y abriendo el
+// campo en áreas más angostas para que la nave no se corte a los lados
+fn frame_camera(camera: &mut Camera, width: usize, height: usize) {
+    camera.fit_viewport(width, height, 45.0_f32.to_radians(), SCREEN_WIDTH as f32 / SCREEN_HEIGHT as f32);
 }
 
-// Función para dibujar los contornos
-fn draw_wireframe(app_state: &mut AppState) {
-    let model = &app_state.model;
-    let center = app_state.center;
-    let scale_factor = app_state.scale_factor;
-    let angle_x = app_state.angle_x;
-    let angle_y = app_state.angle_y;
-    let offset_x = app_state.offset_x;
-    let offset_y = app_state.offset_y;
+// Renderizar un solo cuadro sin abrir ventana y guardarlo como imagen
+fn render_to_file(options: &Options, output: &Path) -> Result<(), String> {
+    let mut app_state = load_app_state(options)?;
     
-    // Color para los contornos
-    let line_color = Color::new(0, 0, 0);  // Negro
+    clear(&mut app_state);
+    render(&mut app_state);
     
-    for face in &model.faces {
-        // Obtener los tres vértices de la cara
-        let v0 = &model.vertices[face[0]];
-        let v1 = &model.vertices[face[1]];
-        let v2 = &model.vertices[face[2]];
-        
-        // Aplicar rotación en X
-        let rotate_x = |v: &Vec3| -> Vec3 {
-            let y = v.y * angle_x.cos() - v.z * angle_x.sin();
-            let z = v.y * angle_x.sin() + v.z * angle_x.cos();
-            Vec3::new(v.x, y, z)
-        };
-        
-        // Aplicar rotación en Y
-        let rotate_y = |v: &Vec3| -> Vec3 {
-            let x = v.x * angle_y.cos() + v.z * angle_y.sin();
-            let z = -v.x * angle_y.sin() + v.z * angle_y.cos();
-            Vec3::new(x, v.y, z)
-        };
-        
-        // Rotar los vértices
-        let rv0 = rotate_y(&rotate_x(v0));
-        let rv1 = rotate_y(&rotate_x(v1));
-        let rv2 = rotate_y(&rotate_x(v2));
-        
-        // Calcular la normal para determinar visibilidad
-        let edge1 = Vec3::new(rv1.x - rv0.x, rv1.y - rv0.y, rv1.z - rv0.z);
-        let edge2 = Vec3::new(rv2.x - rv0.x, rv2.y - rv0.y, rv2.z - rv0.z);
-        let normal = Vec3::new(
-            edge1.y * edge2.z - edge1.z * edge2.y,
-            edge1.z * edge2.x - edge1.x * edge2.z,
-            edge1.x * edge2.y - edge1.y * edge2.x
-        );
-        
-        // Solo procesar caras que miran hacia la cámara
-        if normal.z <= 0.0 {
-            continue;
-        }
-        
-        // Transformar a coordenadas de pantalla
-        let tv0 = Vec3::new(
-            (rv0.x - center.x) * scale_factor + SCREEN_WIDTH as f32 * 0.5 + offset_x,
-            (rv0.y - center.y) * scale_factor + SCREEN_HEIGHT as f32 * 0.5 + offset_y,
-            rv0.z
-        );
-        
-        let tv1 = Vec3::new(
-            (rv1.x - center.x) * scale_factor + SCREEN_WIDTH as f32 * 0.5 + offset_x,
-            (rv1.y - center.y) * scale_factor + SCREEN_HEIGHT as f32 * 0.5 + offset_y,
-            rv1.z
-        );
-        
-        let tv2 = Vec3::new(
-            (rv2.x - center.x) * scale_factor + SCREEN_WIDTH as f32 * 0.5 + offset_x,
-            (rv2.y - center.y) * scale_factor + SCREEN_HEIGHT as f32 * 0.5 + offset_y,
-            rv2.z
-        );
-        
-        // Dibujar las líneas de contorno para caras visibles
-        draw_line(&mut app_state.framebuffer, 
-                tv0.x as i32, tv0.y as i32, 
-                tv1.x as i32, tv1.y as i32, 
-                line_color);
-        
-        draw_line(&mut app_state.framebuffer, 
-                tv1.x as i32, tv1.y as i32, 
-                tv2.x as i32, tv2.y as i32, 
-                line_color);
-        
-        draw_line(&mut app_state.framebuffer, 
-                tv2.x as i32, tv2.y as i32, 
-                tv0.x as i32, tv0.y as i32, 
-                line_color);
-    }
+    app_state.framebuffer.to_image()
+        .save(output)
+        .map_err(|e| format!("no se pudo guardar {}: {}", output.display(), e))?;
+    println!("Imagen guardada en {}", output.display());
+    
+    Ok(())
 }
 
-
-// Función auxiliar para valor absoluto
-fn abs(x: f32) -> f32 {
-    if x < 0.0 { -x } else { x }
+fn clear(app_state: &mut AppState) {
+    app_state.framebuffer.clear(Color::new(0, 0, 0));
 }
 
-// Función para dibujar líneas (algoritmo de Bresenham)
-fn draw_line(framebuffer: &mut Framebuffer, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
-    let mut x = x0;
-    let mut y = y0;
-    
-    let dx = (x1 - x0).abs();
-    let dy = -(y1 - y0).abs();
-    
-    let sx = if x0 < x1 { 1 } else { -1 };
-    let sy = if y0 < y1 { 1 } else { -1 };
-    
-    let mut err = dx + dy;
+fn render(app_state: &mut AppState) {
+    // El destello envuelve al efecto elegido mientras dura
+    let shader = app_state.effect.shader();
+    app_state.renderer.fragment_shader = if app_state.flash > 0.0 {
+        Box::new(FlashShader { inner: shader, color: Vec3::new(1.0, 0.15, 0.1), intensity: app_state.flash * 0.7 })
+    } else {
+        shader
+    };
     
-    loop {
-        if x >= 0 && x < framebuffer.width as i32 && y >= 0 && y < framebuffer.height as i32 {
-            let index = y as usize * framebuffer.width + x as usize;
-            framebuffer.buffer[index] = color;
-        }
-        
-        if x == x1 && y == y1 {
-            break;
-        }
-        
-        let e2 = 2 * err;
-        if e2 >= dy {
-            if x == x1 {
-                break;
-            }
-            err += dy;
-            x += sx;
-        }
-        if e2 <= dx {
-            if y == y1 {
-                break;
-            }
-            err += dx;
-            y += sy;
-        }
-    }
+    let start = Instant::now();
+    app_state.stats = app_state.renderer.render(&app_state.model, &app_state.transform, &mut app_state.framebuffer);
+    app_state.post.apply(&mut app_state.framebuffer);
+    app_state.render_time = start.elapsed();
 }
 
-fn handle_keys(app_state: &mut AppState, keycode: Keycode) {
-    match keycode {
-        Keycode::Left => app_state.angle_y -= 0.1,
-        Keycode::Right => app_state.angle_y += 0.1,
-        Keycode::Up => app_state.angle_x -= 0.1,
-        Keycode::Down => app_state.angle_x += 0.1,
-        Keycode::W => app_state.offset_y -= 10.0,
-        Keycode::S => app_state.offset_y += 10.0,
-        Keycode::A => app_state.offset_x -= 10.0,
-        Keycode::D => app_state.offset_x += 10.0,
-        Keycode::F => {
-            // Girar 180 grados en Y (invertir dirección)
-            app_state.angle_y += std::f32::consts::PI;
-        },
-        Keycode::R => {
-            // Resetear rotación a la posición inicial en lugar de a cero
-            app_state.angle_x = std::f32::consts::PI / 2.0;
-            app_state.angle_y = std::f32::consts::PI;
-            app_state.offset_x = 0.0;
-            app_state.offset_y = 0.0;
-        },
-        _ => {}
+// Estado inicial de un objeto del modelo: los propulsores con su color y su
+// brillo, el resto con los colores del material
+fn initial_part(object: &Object) -> Part {
+    let groups = vec![Part::default(); object.groups.len()];
+    if ENGINE_OBJECTS.contains(&object.name.as_str()) {
+        Part { color: Some(ENGINE_COLOR), emission: ENGINE_GLOW, groups, ..Part::default() }
+    } else {
+        Part { groups, ..Part::default() }
     }
 }
 
 fn main() -> Result<(), String> {
-    let (sdl_context, mut canvas, mut app_state) = init()?;
-    let mut event_pump = sdl_context.event_pump()?;
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return Ok(());
+        },
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        },
+    };
     
-    let mut running = true;
-    while running {
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. } |
-                Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
-                    running = false;
-                },
-                Event::KeyDown { keycode: Some(keycode), .. } => {
-                    handle_keys(&mut app_state, keycode);
-                },
-                _ => {}
-            }
-        }
-        
-        clear(&mut app_state);
-        
-        set_color(&mut app_state, Color::new(255, 255, 0));
-        render(&mut app_state);
-        
-        render_buffer(&mut canvas, &app_state.framebuffer);
-        
-        // Limitar FPS
-        std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    // Con --output no se abre ventana: útil en máquinas sin pantalla
+    if let Some(output) = &options.output {
+        return render_to_file(&options, output);
     }
     
-    Ok(())
+    // Sin la opción `sdl` no hay ventana
+    #[cfg(feature = "sdl")]
+    { window::run(&options) }
+    #[cfg(not(feature = "sdl"))]
+    { Err("compilado sin la opción sdl: usa --output para renderizar a un archivo".to_string()) }
 }
\ No newline at end of file
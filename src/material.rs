@@ -0,0 +1,220 @@
+use std::fs::File;
+use std::io::{self, BufRead};
+use std::path::Path;
+use glm::{Vec2, Vec3};
+use crate::texture::Texture;
+
+// Material de una biblioteca .mtl
+pub struct Material {
+   pub name: String,
+   pub ambient: Vec3,            // Ka
+   pub diffuse: Vec3,            // Kd
+   pub specular: Vec3,           // Ks
+   pub shininess: f32,           // Ns
+   pub emission: Vec3,           // Ke
+   pub opacity: f32,             // d (1 = opaco)
+   pub diffuse_map: Option<Texture>, // map_Kd
+   pub bump_map: Option<Texture>,    // map_Bump / bump
+   pub bump_scale: f32,          // Opción -bm de map_Bump
+}
+
+impl Material {
+   // Valores por defecto de la especificación MTL
+   pub fn new(name: &str) -> Self {
+      Material {
+         name: name.to_string(),
+         ambient: Vec3::new(1.0, 1.0, 1.0),
+         diffuse: Vec3::new(0.8, 0.8, 0.8),
+         specular: Vec3::new(0.0, 0.0, 0.0),
+         shininess: 1.0,
+         emission: Vec3::new(0.0, 0.0, 0.0),
+         opacity: 1.0,
+         diffuse_map: None,
+         bump_map: None,
+         bump_scale: 1.0,
+      }
+   }
+
+   // Perturbar la normal con el mapa de relieve. La altura se lee de la
+   // luminancia de la textura; `tangent` y `bitangent` indican hacia dónde
+   // crecen U y V sobre la superficie. Sin mapa de relieve la normal no cambia.
+   pub fn bump_normal(&self, normal: Vec3, tangent: Vec3, bitangent: Vec3, uv: Vec2) -> Vec3 {
+      let Some(bump_map) = &self.bump_map else {
+         return normal;
+      };
+
+      let base = glm::cross(tangent, bitangent);
+      let base_length = glm::length(base);
+      if base_length == 0.0 {
+         return normal;
+      }
+
+      let height = |uv: Vec2| {
+         let c = bump_map.sample(uv);
+         (c.x + c.y + c.z) / 3.0 * self.bump_scale
+      };
+
+      // Derivadas de la altura respecto a U y V (diferencias de un texel)
+      let du = 1.0 / bump_map.image.width as f32;
+      let dv = 1.0 / bump_map.image.height as f32;
+      let h = height(uv);
+      let h_u = (height(Vec2::new(uv.x + du, uv.y)) - h) / du;
+      let h_v = (height(Vec2::new(uv.x, uv.y + dv)) - h) / dv;
+
+      // Normal de la superficie desplazada h a lo largo de n:
+      // (T + h_u n) x (B + h_v n) = T x B + h_u (n x B) + h_v (T x n)
+      let n = glm::normalize(normal);
+      let orientation = if glm::dot(base, n) < 0.0 { -1.0 } else { 1.0 };
+      let offset = (glm::cross(n, bitangent) * h_u + glm::cross(tangent, n) * h_v) * orientation;
+
+      glm::normalize(n * base_length + offset)
+   }
+}
+
+fn parse_vec3(parts: &[&str]) -> Vec3 {
+   let x = parts.get(1).and_then(|v| v.parse::<f32>().ok()).unwrap_or(0.0);
+   // Un solo valor se repite en los tres canales
+   let y = parts.get(2).and_then(|v| v.parse::<f32>().ok()).unwrap_or(x);
+   let z = parts.get(3).and_then(|v| v.parse::<f32>().ok()).unwrap_or(x);
+   Vec3::new(x, y, z)
+}
+
+// Cargar la textura de una instrucción map_*, que puede traer opciones antes
+// del nombre del archivo (por ejemplo `map_Bump -bm 0.5 casco.png`). La ruta es
+// relativa a la carpeta del .mtl. Si la imagen no se puede leer se agrega un
+// aviso y el material queda sin textura.
+fn load_map(parts: &[&str], directory: &Path, warnings: &mut Vec<String>) -> Option<Texture> {
+   let file = parts.last().filter(|_| parts.len() >= 2)?;
+   let path = directory.join(file);
+   match Texture::load(&path) {
+      Ok(texture) => Some(texture),
+      Err(e) => {
+         warnings.push(format!("no se pudo cargar la textura {}: {}", path.display(), e));
+         None
+      },
+   }
+}
+
+// Leer todos los materiales de un archivo .mtl. Los problemas que no impiden
+// usarlo, como una textura que falta, se agregan a `warnings`.
+pub fn load_mtl<P: AsRef<Path>>(path: P, warnings: &mut Vec<String>) -> io::Result<Vec<Material>> {
+   let path = path.as_ref();
+   parse_mtl(io::BufReader::new(File::open(path)?), path, warnings)
+}
+
+// Leer los materiales desde cualquier lector; las texturas se buscan en la
+// carpeta de `path`
+pub fn parse_mtl<R: BufRead>(reader: R, path: &Path, warnings: &mut Vec<String>) -> io::Result<Vec<Material>> {
+   let directory = path.parent().unwrap_or(Path::new(""));
+   let mut materials: Vec<Material> = Vec::new();
+
+   for line in reader.lines() {
+      let line = line?;
+      let parts: Vec<&str> = line.split_whitespace().collect();
+
+      if parts.is_empty() {
+         continue;
+      }
+
+      if parts[0] == "newmtl" {
+         materials.push(Material::new(parts.get(1).copied().unwrap_or("")));
+         continue;
+      }
+
+      // El resto de instrucciones modifican el último material declarado
+      let Some(material) = materials.last_mut() else {
+         continue;
+      };
+
+      match parts[0] {
+         "Ka" => material.ambient = parse_vec3(&parts),
+         "Kd" => material.diffuse = parse_vec3(&parts),
+         "Ks" => material.specular = parse_vec3(&parts),
+         "Ke" => material.emission = parse_vec3(&parts),
+         "Ns" => material.shininess = parts.get(1).and_then(|v| v.parse().ok()).unwrap_or(1.0),
+         "d" => material.opacity = parts.get(1).and_then(|v| v.parse().ok()).unwrap_or(1.0),
+         // Tr es la transparencia: lo contrario de d
+         "Tr" => material.opacity = 1.0 - parts.get(1).and_then(|v| v.parse::<f32>().ok()).unwrap_or(0.0),
+         "map_Kd" => material.diffuse_map = load_map(&parts, directory, warnings),
+         "map_Bump" | "map_bump" | "bump" => {
+            material.bump_map = load_map(&parts, directory, warnings);
+            if let Some(i) = parts.iter().position(|p| *p == "-bm") {
+               material.bump_scale = parts.get(i + 1).and_then(|v| v.parse().ok()).unwrap_or(1.0);
+            }
+         },
+         _ => {}
+      }
+   }
+
+   Ok(materials)
+}
+
+#[cfg(test)]
+mod tests {
+   use std::path::Path;
+   use glm::Vec3;
+   use super::{parse_mtl, Material};
+
+   fn parse(source: &str) -> (Vec<Material>, Vec<String>) {
+      let mut warnings = Vec::new();
+      let materials = parse_mtl(source.as_bytes(), Path::new("prueba.mtl"), &mut warnings).unwrap();
+      (materials, warnings)
+   }
+
+   fn same(a: Vec3, b: Vec3) -> bool {
+      (a.x - b.x).abs() < 1e-6 && (a.y - b.y).abs() < 1e-6 && (a.z - b.z).abs() < 1e-6
+   }
+
+   #[test]
+   fn colors_and_scalars() {
+      let (materials, warnings) = parse("\
+# instrucciones antes de newmtl se ignoran
+Kd 1 0 0
+newmtl casco
+Kd 0.1 0.2 0.3
+Ks 0.5
+Ke 2 1 0
+Ns 96
+d 0.25
+newmtl vidrio
+Tr 0.75
+illum 2
+Pr 0.4
+newmtl nuevo
+");
+      assert!(warnings.is_empty(), "{:?}", warnings);
+      let names: Vec<&str> = materials.iter().map(|m| m.name.as_str()).collect();
+      assert_eq!(names, ["casco", "vidrio", "nuevo"]);
+
+      let casco = &materials[0];
+      assert!(same(casco.diffuse, Vec3::new(0.1, 0.2, 0.3)));
+      // Un solo valor vale para los tres canales
+      assert!(same(casco.specular, Vec3::new(0.5, 0.5, 0.5)));
+      assert!(same(casco.emission, Vec3::new(2.0, 1.0, 0.0)));
+      assert_eq!((casco.shininess, casco.opacity), (96.0, 0.25));
+
+      // Tr es lo contrario de d; las instrucciones desconocidas no cambian nada
+      assert_eq!(materials[1].opacity, 0.25);
+      assert!(same(materials[1].diffuse, Vec3::new(0.8, 0.8, 0.8)));
+
+      // Sin instrucciones quedan los valores por defecto
+      let nuevo = &materials[2];
+      assert!(same(nuevo.diffuse, Vec3::new(0.8, 0.8, 0.8)) && same(nuevo.specular, Vec3::new(0.0, 0.0, 0.0)));
+      assert_eq!((nuevo.shininess, nuevo.opacity), (1.0, 1.0));
+   }
+
+   #[test]
+   fn bump_map_options_and_missing_textures() {
+      let (materials, warnings) = parse("\
+newmtl casco
+map_Bump -bm 0.5 relieve.png
+map_Kd pintura.png
+");
+      let casco = &materials[0];
+      assert_eq!(casco.bump_scale, 0.5);
+      assert!(casco.bump_map.is_none() && casco.diffuse_map.is_none());
+      assert_eq!(warnings.len(), 2);
+      assert!(warnings[0].starts_with("no se pudo cargar la textura relieve.png"), "{}", warnings[0]);
+      assert!(warnings[1].starts_with("no se pudo cargar la textura pintura.png"), "{}", warnings[1]);
+   }
+}
@@ -0,0 +1,60 @@
+// Máximo de muestras por pixel
+pub const MAX_SAMPLES: usize = 8;
+
+// Antialiasing multimuestra: la cobertura y la profundidad de cada triángulo
+// se evalúan en varias muestras por pixel, el color se calcula una sola vez
+// por pixel y al final se promedian las muestras
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msaa {
+   Off,
+   X2,
+   X4,
+   X8,
+}
+
+impl Msaa {
+   pub fn samples(self) -> usize {
+      self.pattern().len()
+   }
+
+   pub fn name(self) -> &'static str {
+      match self {
+         Msaa::Off => "sin MSAA",
+         Msaa::X2 => "MSAA 2x",
+         Msaa::X4 => "MSAA 4x",
+         Msaa::X8 => "MSAA 8x",
+      }
+   }
+
+   pub fn next(self) -> Self {
+      match self {
+         Msaa::Off => Msaa::X2,
+         Msaa::X2 => Msaa::X4,
+         Msaa::X4 => Msaa::X8,
+         Msaa::X8 => Msaa::Off,
+      }
+   }
+
+   // Cantidad de muestras dada como número (1 = sin MSAA)
+   pub fn from_samples(samples: usize) -> Option<Self> {
+      match samples {
+         1 => Some(Msaa::Off),
+         2 => Some(Msaa::X2),
+         4 => Some(Msaa::X4),
+         8 => Some(Msaa::X8),
+         _ => None,
+      }
+   }
+
+   // Posición de cada muestra respecto del centro del pixel, en dieciseisavos
+   // de pixel. Son los patrones estándar de Direct3D: rotados, para que las
+   // aristas casi horizontales o verticales también tengan varios niveles.
+   pub fn pattern(self) -> &'static [(i32, i32)] {
+      match self {
+         Msaa::Off => &[(0, 0)],
+         Msaa::X2 => &[(4, 4), (-4, -4)],
+         Msaa::X4 => &[(-2, -6), (6, -2), (-6, 2), (2, 6)],
+         Msaa::X8 => &[(1, -3), (-1, 3), (5, 1), (-3, -5), (-5, 5), (-7, -1), (3, 7), (7, -7)],
+      }
+   }
+}
@@ -1,23 +1,219 @@
+use std::fmt;
 use std::fs::File;
 use std::io::{self, BufRead};
-use std::path::Path;
-use glm::Vec3;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use glm::{Vec2, Vec3};
+use crate::material::{load_mtl, Material};
+
+// Ángulo a partir del cual una arista se considera "dura" al suavizar normales
+pub const SMOOTHING_ANGLE: f32 = std::f32::consts::PI / 3.0;
+
+// Error al leer un .obj: archivo, línea (0 si el problema no es de una línea
+// concreta, por ejemplo al abrir el archivo) y el motivo
+#[derive(Debug)]
+pub struct ObjError {
+   pub file: PathBuf,
+   pub line: usize,
+   pub reason: String,
+}
+
+impl fmt::Display for ObjError {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      if self.line == 0 {
+         write!(f, "{}: {}", self.file.display(), self.reason)
+      } else {
+         write!(f, "{}:{}: {}", self.file.display(), self.line, self.reason)
+      }
+   }
+}
+
+impl std::error::Error for ObjError {}
+
+// Triángulo del modelo: índices de posición, normal y coordenadas de
+// textura (si las tiene) de cada esquina, y el material asignado con usemtl
+#[derive(Debug, Clone, Copy)]
+pub struct Face {
+   pub vertices: [usize; 3],
+   pub normals: [usize; 3],
+   pub texcoords: Option<[usize; 3]>,
+   pub material: Option<usize>,
+}
+
+// Grupo (`g`) de caras dentro de un objeto. Las caras de un grupo son
+// consecutivas en `Model::faces`.
+#[derive(Debug, Clone)]
+pub struct Group {
+   pub name: String,
+   pub faces: Range<usize>,
+}
+
+// Objeto (`o`) del modelo, formado por uno o más grupos. Las caras que no están
+// dentro de ningún `o` van a un objeto llamado "default", y las que no están
+// dentro de ningún `g` a un grupo sin nombre.
+#[derive(Debug, Clone)]
+pub struct Object {
+   pub name: String,
+   pub groups: Vec<Group>,
+}
+
+impl Object {
+   // Todas las caras del objeto
+   pub fn faces(&self) -> Range<usize> {
+      match (self.groups.first(), self.groups.last()) {
+         (Some(first), Some(last)) => first.faces.start..last.faces.end,
+         _ => 0..0,
+      }
+   }
+}
 
 pub struct Model {
    pub vertices: Vec<Vec3>,
-   pub faces: Vec<[usize; 3]>,
+   pub normals: Vec<Vec3>,
+   pub texcoords: Vec<Vec2>,
+   pub faces: Vec<Face>,
+   pub materials: Vec<Material>,
+   pub objects: Vec<Object>,
+   // Todas las caras se declararon con sombreado plano (s 0 o s off), así que
+   // sus normales, si las trae, son una por cara
+   pub flat_shading: bool,
+   // Problemas que no impidieron cargar el modelo (una biblioteca de
+   // materiales o una textura que no se pudo leer), para que el programa los
+   // muestre
+   pub warnings: Vec<String>,
+}
+
+// Leer hasta N números a partir de parts[1]. Los primeros `required` son
+// obligatorios, los que faltan quedan en 0 y los que sobran (por ejemplo la w
+// de `v x y z w`) se ignoran.
+fn parse_floats<const N: usize>(parts: &[&str], required: usize) -> Result<[f32; N], String> {
+   if parts.len() - 1 < required {
+      return Err(format!("'{}' necesita al menos {} valores", parts[0], required));
+   }
+   
+   let mut values = [0.0; N];
+   for (value, text) in values.iter_mut().zip(&parts[1..]) {
+      *value = text.parse::<f32>().map_err(|_| format!("número inválido '{}'", text))?;
+   }
+   Ok(values)
+}
+
+// Convertir un índice del archivo (1 es el primero, -1 el último leído hasta
+// ahora) a un índice desde 0, comprobando que exista
+fn resolve_index(text: &str, count: usize, kind: &str) -> Result<usize, String> {
+   let index = text.parse::<i64>().map_err(|_| format!("índice de {} inválido '{}'", kind, text))?;
+   
+   let resolved = if index > 0 {
+      index - 1
+   } else if index < 0 {
+      count as i64 + index
+   } else {
+      return Err(format!("índice de {} 0 (los índices empiezan en 1)", kind));
+   };
+   
+   if resolved < 0 || resolved >= count as i64 {
+      return Err(format!("índice de {} {} fuera de rango (hay {})", kind, index, count));
+   }
+   Ok(resolved as usize)
+}
+
+// Dividir un polígono plano en triángulos por recorte de orejas, para que
+// también los polígonos cóncavos queden bien. Devuelve índices dentro de
+// `points`. Si el polígono está tan deformado que no se encuentran orejas se
+// termina con un abanico desde el primer vértice.
+fn triangulate_polygon(points: &[Vec3]) -> Vec<[usize; 3]> {
+   let n = points.len();
+   if n == 3 {
+      return vec![[0, 1, 2]];
+   }
+   
+   // Normal del polígono (método de Newell) para proyectarlo al plano en que
+   // se ve más grande
+   let mut normal = Vec3::new(0.0, 0.0, 0.0);
+   for i in 0..n {
+      let a = points[i];
+      let b = points[(i + 1) % n];
+      normal.x += (a.y - b.y) * (a.z + b.z);
+      normal.y += (a.z - b.z) * (a.x + b.x);
+      normal.z += (a.x - b.x) * (a.y + b.y);
+   }
+   let (ax, ay) = if normal.x.abs() >= normal.y.abs() && normal.x.abs() >= normal.z.abs() {
+      (1, 2)
+   } else if normal.y.abs() >= normal.z.abs() {
+      (2, 0)
+   } else {
+      (0, 1)
+   };
+   let sign = [normal.x, normal.y, normal.z][3 - ax - ay].signum();
+   let projected: Vec<Vec2> = points.iter().map(|p| Vec2::new(p[ax], p[ay])).collect();
+   
+   // Área con signo: positiva si el giro coincide con el del polígono
+   let cross = |a: Vec2, b: Vec2, c: Vec2| ((b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)) * sign;
+   
+   let mut remaining: Vec<usize> = (0..n).collect();
+   let mut triangles = Vec::with_capacity(n - 2);
+   
+   while remaining.len() > 3 {
+      let m = remaining.len();
+      let ear = (0..m).find(|&i| {
+         let prev = remaining[(i + m - 1) % m];
+         let current = remaining[i];
+         let next = remaining[(i + 1) % m];
+         let (a, b, c) = (projected[prev], projected[current], projected[next]);
+         
+         // La esquina debe ser convexa y no contener ningún otro vértice
+         cross(a, b, c) > 0.0 && remaining.iter()
+            .filter(|&&j| j != prev && j != current && j != next)
+            .all(|&j| {
+               let p = projected[j];
+               cross(a, b, p) < 0.0 || cross(b, c, p) < 0.0 || cross(c, a, p) < 0.0
+            })
+      });
+      
+      let Some(i) = ear else {
+         break;
+      };
+      triangles.push([remaining[(i + m - 1) % m], remaining[i], remaining[(i + 1) % m]]);
+      remaining.remove(i);
+   }
+   
+   for i in 1..remaining.len() - 1 {
+      triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+   }
+   
+   triangles
 }
 
 impl Model {
-   pub fn load_obj<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-      let file = File::open(path)?;
-      let reader = io::BufReader::new(file);
+   pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<Self, ObjError> {
+      let path = path.as_ref();
+      let file = File::open(path)
+         .map_err(|e| ObjError { file: path.to_path_buf(), line: 0, reason: e.to_string() })?;
+      Self::parse_obj(io::BufReader::new(file), path)
+   }
+   
+   // Leer un .obj desde cualquier lector. `path` se usa en los errores y para
+   // encontrar las bibliotecas de materiales, que son relativas a su carpeta.
+   pub fn parse_obj<R: BufRead>(reader: R, path: &Path) -> Result<Self, ObjError> {
+      let directory = path.parent().unwrap_or(Path::new(""));
+      let error = |line: usize, reason: String| ObjError { file: path.to_path_buf(), line, reason };
       
       let mut vertices = Vec::new();
+      let mut normals = Vec::new();
+      let mut texcoords = Vec::new();
       let mut faces = Vec::new();
+      let mut materials: Vec<Material> = Vec::new();
+      let mut objects: Vec<Object> = Vec::new();
+      let mut current_material = None;
+      let mut missing_normals = false;
+      let mut warnings = Vec::new();
+      // Grupo de suavizado actual: None hasta la primera instrucción `s`
+      let mut smoothing: Option<bool> = None;
+      let mut flat_shading = true;
       
-      for line in reader.lines() {
-         let line = line?;
+      for (number, line) in reader.lines().enumerate() {
+         let number = number + 1;
+         let line = line.map_err(|e| error(number, e.to_string()))?;
          let parts: Vec<&str> = line.split_whitespace().collect();
          
          if parts.is_empty() {
@@ -26,28 +222,179 @@ impl Model {
          
          match parts[0] {
                "v" => {
-                  if parts.len() >= 4 {
-                     let x = parts[1].parse::<f32>().unwrap_or(0.0);
-                     let y = parts[2].parse::<f32>().unwrap_or(0.0);
-                     let z = parts[3].parse::<f32>().unwrap_or(0.0);
-                     vertices.push(Vec3::new(x, y, z));
-                  }
+                  let [x, y, z] = parse_floats(&parts, 3).map_err(|e| error(number, e))?;
+                  vertices.push(Vec3::new(x, y, z));
+               },
+               "vt" => {
+                  // La v es opcional en las texturas de una dimensión
+                  let [u, v] = parse_floats(&parts, 1).map_err(|e| error(number, e))?;
+                  texcoords.push(Vec2::new(u, v));
+               },
+               "vn" => {
+                  let [x, y, z] = parse_floats(&parts, 3).map_err(|e| error(number, e))?;
+                  normals.push(Vec3::new(x, y, z));
                },
                "f" => {
-                  if parts.len() >= 4 {
-                     // Los índices en archivos .obj empiezan en 1, así que restamos 1
-                     let v1 = parts[1].split('/').next().unwrap_or("1").parse::<usize>().unwrap_or(1) - 1;
-                     let v2 = parts[2].split('/').next().unwrap_or("1").parse::<usize>().unwrap_or(1) - 1;
-                     let v3 = parts[3].split('/').next().unwrap_or("1").parse::<usize>().unwrap_or(1) - 1;
+                  if parts.len() < 4 {
+                     return Err(error(number, format!("la cara tiene {} vértices, se necesitan al menos 3", parts.len() - 1)));
+                  }
+                  
+                  // Cada esquina tiene la forma v, v/vt, v//vn o v/vt/vn
+                  let mut corner_vertices = Vec::with_capacity(parts.len() - 1);
+                  let mut corner_texcoords = Some(Vec::with_capacity(parts.len() - 1));
+                  let mut corner_normals = Some(Vec::with_capacity(parts.len() - 1));
+                  for corner in &parts[1..] {
+                     let mut fields = corner.split('/');
+                     let v = fields.next().unwrap_or("");
+                     corner_vertices.push(resolve_index(v, vertices.len(), "vértice").map_err(|e| error(number, e))?);
+                     
+                     match fields.next().filter(|t| !t.is_empty()) {
+                        Some(t) => {
+                           let t = resolve_index(t, texcoords.len(), "textura").map_err(|e| error(number, e))?;
+                           if let Some(uvs) = corner_texcoords.as_mut() {
+                              uvs.push(t);
+                           }
+                        },
+                        None => corner_texcoords = None,
+                     }
                      
-                     faces.push([v1, v2, v3]);
+                     match fields.next().filter(|n| !n.is_empty()) {
+                        Some(n) => {
+                           let n = resolve_index(n, normals.len(), "normal").map_err(|e| error(number, e))?;
+                           if let Some(ns) = corner_normals.as_mut() {
+                              ns.push(n);
+                           }
+                        },
+                        None => corner_normals = None,
+                     }
                   }
+                  
+                  if corner_normals.is_none() {
+                     missing_normals = true;
+                  }
+                  if smoothing != Some(false) {
+                     flat_shading = false;
+                  }
+                  
+                  // La cara pertenece al último objeto y grupo declarados
+                  if objects.is_empty() {
+                     objects.push(Object { name: "default".to_string(), groups: Vec::new() });
+                  }
+                  let object = objects.last_mut().unwrap();
+                  if object.groups.is_empty() {
+                     object.groups.push(Group { name: String::new(), faces: faces.len()..faces.len() });
+                  }
+                  
+                  // Los cuadriláteros y n-gonos se dividen en triángulos
+                  let points: Vec<Vec3> = corner_vertices.iter().map(|&v| vertices[v]).collect();
+                  for [a, b, c] in triangulate_polygon(&points) {
+                     faces.push(Face {
+                        vertices: [corner_vertices[a], corner_vertices[b], corner_vertices[c]],
+                        normals: corner_normals.as_ref().map_or([0; 3], |n| [n[a], n[b], n[c]]),
+                        texcoords: corner_texcoords.as_ref().map(|t| [t[a], t[b], t[c]]),
+                        material: current_material,
+                     });
+                  }
+                  object.groups.last_mut().unwrap().faces.end = faces.len();
+               },
+               "o" => {
+                  objects.push(Object { name: parts[1..].join(" "), groups: Vec::new() });
+               },
+               "g" => {
+                  if objects.is_empty() {
+                     objects.push(Object { name: "default".to_string(), groups: Vec::new() });
+                  }
+                  let group = Group { name: parts[1..].join(" "), faces: faces.len()..faces.len() };
+                  objects.last_mut().unwrap().groups.push(group);
+               },
+               "mtllib" if parts.len() >= 2 => {
+                  // La biblioteca es relativa a la carpeta del .obj. Si falta, el
+                  // modelo se carga igual, con un aviso, y las caras quedan sin
+                  // material.
+                  let mtl_path = directory.join(parts[1..].join(" "));
+                  match load_mtl(&mtl_path, &mut warnings) {
+                     Ok(loaded) => materials.extend(loaded),
+                     Err(e) => warnings.push(format!("no se pudo cargar {}: {}", mtl_path.display(), e)),
+                  }
+               },
+               "s" if parts.len() >= 2 => {
+                  // `s 0` y `s off` apagan el suavizado; cualquier otro número
+                  // es un grupo de suavizado
+                  smoothing = Some(parts[1] != "0" && parts[1] != "off");
+               },
+               "usemtl" => {
+                  // Un nombre vacío o desconocido deja las caras sin material
+                  let name = parts[1..].join(" ");
+                  current_material = materials.iter().position(|m| m.name == name);
                },
                _ => {}
          }
       }
       
-      Ok(Model { vertices, faces })
+      // Descartar grupos y objetos que no llegaron a tener caras
+      for object in &mut objects {
+         object.groups.retain(|g| !g.faces.is_empty());
+      }
+      objects.retain(|o| !o.groups.is_empty());
+      
+      let flat_shading = flat_shading && !faces.is_empty();
+      let mut model = Model { vertices, normals, texcoords, faces, materials, objects, flat_shading, warnings };
+      
+      // Si el archivo no trae normales para todas las caras, calcularlas
+      if missing_normals {
+         model.compute_smooth_normals(SMOOTHING_ANGLE);
+      }
+      
+      Ok(model)
+   }
+   
+   // Normal geométrica (no normalizada) de una cara; su longitud es el doble del área
+   pub fn face_normal(&self, face: &Face) -> Vec3 {
+      let v0 = self.vertices[face.vertices[0]];
+      let v1 = self.vertices[face.vertices[1]];
+      let v2 = self.vertices[face.vertices[2]];
+      glm::cross(v1 - v0, v2 - v0)
+   }
+   
+   // Recalcular las normales por vértice promediando las normales de las caras
+   // que comparten cada posición (ponderadas por área). Solo se promedian caras
+   // cuya normal forme con la de la cara actual un ángulo menor a `crease_angle`,
+   // para que las aristas marcadas sigan viéndose duras.
+   pub fn compute_smooth_normals(&mut self, crease_angle: f32) {
+      let face_normals: Vec<Vec3> = self.faces.iter().map(|face| self.face_normal(face)).collect();
+      
+      // Caras que tocan cada posición
+      let mut adjacent = vec![Vec::new(); self.vertices.len()];
+      for (i, face) in self.faces.iter().enumerate() {
+         for &v in &face.vertices {
+            adjacent[v].push(i);
+         }
+      }
+      
+      let cos_crease = crease_angle.cos();
+      let mut normals = Vec::with_capacity(self.faces.len() * 3);
+      
+      for (i, face) in self.faces.iter_mut().enumerate() {
+         let own = face_normals[i];
+         
+         for corner in 0..3 {
+            let mut sum = Vec3::new(0.0, 0.0, 0.0);
+            for &other in &adjacent[face.vertices[corner]] {
+               let n = face_normals[other];
+               // Comparar cosenos sin normalizar, para no dividir entre cero en
+               // caras degeneradas
+               if glm::dot(own, n) >= cos_crease * glm::length(own) * glm::length(n) {
+                  sum = sum + n;
+               }
+            }
+            
+            let length = glm::length(sum);
+            face.normals[corner] = normals.len();
+            normals.push(if length > 0.0 { sum / length } else { sum });
+         }
+      }
+      
+      self.normals = normals;
    }
    
    // Calcular el centro del modelo
@@ -93,4 +440,79 @@ impl Model {
          max.z - min.z
       )
    }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+   use std::path::Path;
+   use super::{Model, ObjError};
+
+   fn parse(source: &str) -> Result<Model, ObjError> {
+      Model::parse_obj(source.as_bytes(), Path::new("prueba.obj"))
+   }
+
+   #[test]
+   fn concave_polygons_are_ear_clipped() {
+      // Una U: un abanico desde el primer vértice saldría del polígono
+      let model = parse("\
+v 0 0 0\nv 3 0 0\nv 3 3 0\nv 2 3 0\nv 2 1 0\nv 1 1 0\nv 1 3 0\nv 0 3 0
+f 1 2 3 4 5 6 7 8
+").unwrap();
+      assert_eq!(model.faces.len(), 6);
+      
+      // Todos los triángulos giran como el polígono y juntos cubren su área
+      let mut area = 0.0;
+      for face in &model.faces {
+         let doubled = model.face_normal(face).z;
+         assert!(doubled > 0.0, "triángulo {:?} invertido o degenerado", face.vertices);
+         area += doubled / 2.0;
+      }
+      assert!((area - 7.0).abs() < 1e-5, "los triángulos cubren {} en lugar de 7", area);
+   }
+
+   #[test]
+   fn negative_indices_count_back_from_the_last_element() {
+      let model = parse("\
+v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvn 0 0 1
+f -3/-1/-1 -2/-1/-1 -1/-1/-1
+v 1 1 0
+f -1//-1 -2//-1 -3//-1
+").unwrap();
+      assert_eq!(model.faces[0].vertices, [0, 1, 2]);
+      assert_eq!(model.faces[0].texcoords, Some([0, 0, 0]));
+      assert_eq!(model.faces[0].normals, [0, 0, 0]);
+      assert_eq!(model.faces[1].vertices, [3, 2, 1]);
+   }
+
+   #[test]
+   fn errors_report_line_and_reason() {
+      let cases = [
+         ("v 0 0 0\nv 1 x 0\n", 2, "número inválido 'x'"),
+         ("v 0 0 0\nv 1 0 0\nv 0 1 0\n\nf 1 2 5\n", 5, "índice de vértice 5 fuera de rango (hay 3)"),
+         ("# vértice sin z\nv 1 2\n", 2, "'v' necesita al menos 3 valores"),
+      ];
+      for (source, line, reason) in cases {
+         let error = parse(source).err().expect("se esperaba un error");
+         assert_eq!((error.line, error.reason.as_str()), (line, reason));
+         assert_eq!(error.to_string(), format!("prueba.obj:{}: {}", line, reason));
+      }
+   }
+
+   #[test]
+   fn flat_shading_needs_every_face_in_s_off() {
+      let triangle = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+      let flat = |statements: &str| parse(&format!("{}{}", triangle, statements)).unwrap().flat_shading;
+      assert!(flat("s 0\nf 1 2 3\n"));
+      assert!(flat("s off\nf 1 2 3\ns 0\nf 3 2 1\n"));
+      assert!(!flat("f 1 2 3\n"));
+      assert!(!flat("s 0\nf 1 2 3\ns 1\nf 3 2 1\n"));
+   }
+
+   #[test]
+   fn missing_material_library_is_a_warning() {
+      let model = parse("mtllib no_existe.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl casco\nf 1 2 3\n").unwrap();
+      assert_eq!(model.faces[0].material, None);
+      assert_eq!(model.warnings.len(), 1);
+      assert!(model.warnings[0].starts_with("no se pudo cargar no_existe.mtl"), "{}", model.warnings[0]);
+   }
+}
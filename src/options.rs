@@ -0,0 +1,280 @@
+use std::path::PathBuf;
+use space_ship_render::color::Color;
+use space_ship_render::framebuffer::{SCREEN_WIDTH, SCREEN_HEIGHT};
+use space_ship_render::hdr::{ToneMapper, ToneMapping};
+use space_ship_render::tiles::default_threads;
+use space_ship_render::line::{LineCap, LineStyle};
+use space_ship_render::msaa::Msaa;
+use space_ship_render::postprocess::{PostChain, PostEffect, PostPass};
+use space_ship_render::wireframe::RenderMode;
+
+pub const USAGE: &str = "\
+Uso: space_ship_render [opciones]
+
+Opciones:
+  --model <archivo>     Modelo .obj a cargar (por defecto models/NavePrototipo2.obj)
+  --width <pixeles>     Ancho de la imagen (por defecto 800)
+  --height <pixeles>    Alto de la imagen (por defecto 600)
+  --angle-x <grados>    Rotación inicial en X (por defecto -90)
+  --angle-y <grados>    Rotación inicial en Y (por defecto 180)
+  --output <archivo>    Renderizar sin ventana y guardar la imagen (.png o .ppm)
+  --threads <n>         Hilos para rasterizar (por defecto uno por núcleo;
+                        1 dibuja sin tiles)
+  --present <formato>   Formato de la textura de la ventana: rgb24 o rgba8888
+                        (por defecto rgb24)
+  --mode <modo>         Qué dibujar: solid, wireframe, solid-wire o
+                        hidden-line (por defecto solid-wire)
+  --line-color <RRGGBB> Color de las aristas en hexadecimal (por defecto 000000)
+  --line-width <pixeles>
+                        Grosor de las aristas, puede tener decimales (por
+                        defecto 1)
+  --line-cap <forma>    Extremos de las aristas: butt, square o round (por
+                        defecto round)
+  --line-aa <on|off>    Suavizar las aristas (por defecto on)
+  --msaa <muestras>     Muestras por pixel para suavizar los bordes de las
+                        caras: 1, 2, 4 u 8 (por defecto 1, sin MSAA)
+  --post <lista>        Efectos sobre la imagen terminada, separados por
+                        comas y en el orden en que se aplican: fxaa,
+                        sharpen, vignette, chromatic y grade (por defecto
+                        ninguno)
+  --lut <archivo>       Tabla .cube para la gradación de color (grade)
+  --hdr <curva>         Calcular la luz en punto flotante y comprimirla con
+                        reinhard o aces; off recorta cada pixel a 8 bits
+                        (por defecto off)
+  --exposure <pasos>    Exposición con HDR en pasos (EV), puede ser negativa
+                        (por defecto 0)
+  -h, --help            Mostrar esta ayuda";
+
+// Formato de la textura de la ventana
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PresentFormat {
+   // 3 bytes por pixel: se sube el framebuffer tal cual, sin convertirlo
+   Rgb24,
+   // Un u32 por pixel (0xRRGGBBAA). Cuesta empaquetarlo, pero es el formato
+   // nativo de muchas tarjetas y SDL no tiene que convertirlo al dibujar.
+   Rgba8888,
+}
+
+// Opciones de la línea de comandos
+pub struct Options {
+   pub model: PathBuf,
+   pub width: usize,
+   pub height: usize,
+   // Ángulos en radianes
+   pub angle_x: f32,
+   pub angle_y: f32,
+   // Si se indica, se renderiza un solo cuadro sin ventana y se guarda aquí
+   pub output: Option<PathBuf>,
+   pub present_format: PresentFormat,
+   pub threads: usize,
+   pub render_mode: RenderMode,
+   pub line_style: LineStyle,
+   pub msaa: Msaa,
+   // Todos los efectos de post-proceso: primero los pedidos con --post,
+   // encendidos, y después el resto apagados
+   pub post: PostChain,
+   pub lut: Option<PathBuf>,
+   pub hdr: bool,
+   pub tone_mapper: ToneMapper,
+}
+
+impl Default for Options {
+   fn default() -> Self {
+      Options {
+         model: PathBuf::from("models/NavePrototipo2.obj"),
+         width: SCREEN_WIDTH,
+         height: SCREEN_HEIGHT,
+         angle_x: -std::f32::consts::PI / 2.0,
+         angle_y: std::f32::consts::PI,
+         output: None,
+         present_format: PresentFormat::Rgb24,
+         threads: default_threads(),
+         render_mode: RenderMode::SolidWireframe,
+         line_style: LineStyle { color: Color::new(0, 0, 0), width: 1.0, cap: LineCap::Round, antialiased: true },
+         msaa: Msaa::Off,
+         post: PostChain::standard(),
+         lut: None,
+         hdr: false,
+         tone_mapper: ToneMapper::default(),
+      }
+   }
+}
+
+impl Options {
+   // Leer las opciones (sin el nombre del programa). Devuelve `None` si se
+   // pidió la ayuda.
+   pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Option<Self>, String> {
+      let mut options = Options::default();
+      let mut args = args.into_iter();
+
+      while let Some(arg) = args.next() {
+         if arg == "-h" || arg == "--help" {
+            return Ok(None);
+         }
+
+         if !["--model", "--width", "--height", "--angle-x", "--angle-y", "--output", "--threads", "--present", "--mode", "--line-color", "--line-width", "--line-cap", "--line-aa", "--msaa", "--post", "--lut", "--hdr", "--exposure"].contains(&arg.as_str()) {
+            return Err(format!("opción desconocida: {}\n\n{}", arg, USAGE));
+         }
+
+         let value = args.next().ok_or_else(|| format!("falta el valor de {}", arg))?;
+         let size = |v: &str| match v.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(format!("{} debe ser un entero positivo, no '{}'", arg, v)),
+         };
+         let degrees = |v: &str| {
+            v.parse::<f32>()
+               .map(f32::to_radians)
+               .map_err(|_| format!("{} debe ser un número, no '{}'", arg, v))
+         };
+
+         match arg.as_str() {
+            "--model" => options.model = PathBuf::from(value),
+            "--width" => options.width = size(&value)?,
+            "--height" => options.height = size(&value)?,
+            "--angle-x" => options.angle_x = degrees(&value)?,
+            "--angle-y" => options.angle_y = degrees(&value)?,
+            "--output" => {
+               // Image::save elige el formato por la extensión, así que se
+               // comprueba antes de renderizar
+               let path = PathBuf::from(value);
+               let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+               if !matches!(extension.as_deref(), Some("png" | "ppm")) {
+                  return Err(format!("--output debe terminar en .png o .ppm, no '{}'", path.display()));
+               }
+               options.output = Some(path);
+            },
+            "--threads" => options.threads = size(&value)?,
+            "--present" => {
+               options.present_format = match value.as_str() {
+                  "rgb24" => PresentFormat::Rgb24,
+                  "rgba8888" => PresentFormat::Rgba8888,
+                  _ => return Err(format!("--present debe ser rgb24 o rgba8888, no '{}'", value)),
+               }
+            },
+            "--mode" => {
+               options.render_mode = match value.as_str() {
+                  "solid" => RenderMode::Solid,
+                  "wireframe" => RenderMode::Wireframe,
+                  "solid-wire" => RenderMode::SolidWireframe,
+                  "hidden-line" => RenderMode::HiddenLine,
+                  _ => return Err(format!("--mode debe ser solid, wireframe, solid-wire o hidden-line, no '{}'", value)),
+               }
+            },
+            "--line-color" => {
+               let hex = value.strip_prefix('#').unwrap_or(&value);
+               let rgb = match u32::from_str_radix(hex, 16) {
+                  Ok(rgb) if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) => rgb,
+                  _ => return Err(format!("--line-color debe ser un color RRGGBB en hexadecimal, no '{}'", value)),
+               };
+               options.line_style.color = Color::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8);
+            },
+            "--line-width" => {
+               options.line_style.width = match value.parse::<f32>() {
+                  Ok(width) if width > 0.0 && width.is_finite() => width,
+                  _ => return Err(format!("--line-width debe ser un número positivo, no '{}'", value)),
+               }
+            },
+            "--line-cap" => {
+               options.line_style.cap = match value.as_str() {
+                  "butt" => LineCap::Butt,
+                  "square" => LineCap::Square,
+                  "round" => LineCap::Round,
+                  _ => return Err(format!("--line-cap debe ser butt, square o round, no '{}'", value)),
+               }
+            },
+            "--line-aa" => {
+               options.line_style.antialiased = match value.as_str() {
+                  "on" => true,
+                  "off" => false,
+                  _ => return Err(format!("--line-aa debe ser on u off, no '{}'", value)),
+               }
+            },
+            "--msaa" => {
+               options.msaa = value.parse().ok().and_then(Msaa::from_samples)
+                  .ok_or_else(|| format!("--msaa debe ser 1, 2, 4 u 8, no '{}'", value))?;
+            },
+            "--post" => {
+               let mut passes: Vec<PostPass> = Vec::new();
+               for id in value.split(',') {
+                  let effect = PostEffect::from_id(id)
+                     .ok_or_else(|| format!("--post: efecto desconocido '{}' (fxaa, sharpen, vignette, chromatic o grade)", id))?;
+                  if passes.iter().any(|pass| pass.effect.id() == id) {
+                     return Err(format!("--post: el efecto '{}' está repetido", id));
+                  }
+                  passes.push(PostPass { effect, enabled: true });
+               }
+               for pass in PostChain::standard().passes {
+                  if !passes.iter().any(|p| p.effect.id() == pass.effect.id()) {
+                     passes.push(pass);
+                  }
+               }
+               options.post.passes = passes;
+            },
+            "--lut" => options.lut = Some(PathBuf::from(value)),
+            "--hdr" => {
+               (options.hdr, options.tone_mapper.tone_mapping) = match value.as_str() {
+                  "off" => (false, options.tone_mapper.tone_mapping),
+                  "reinhard" => (true, ToneMapping::Reinhard),
+                  "aces" => (true, ToneMapping::Aces),
+                  _ => return Err(format!("--hdr debe ser off, reinhard o aces, no '{}'", value)),
+               }
+            },
+            "--exposure" => {
+               options.tone_mapper.exposure = match value.parse::<f32>() {
+                  Ok(exposure) if exposure.is_finite() => exposure,
+                  _ => return Err(format!("--exposure debe ser un número, no '{}'", value)),
+               }
+            },
+            _ => unreachable!(),
+         }
+      }
+
+      Ok(Some(options))
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use std::path::Path;
+   use space_ship_render::msaa::Msaa;
+   use super::Options;
+
+   fn parse(args: &[&str]) -> Result<Option<Options>, String> {
+      Options::parse(args.iter().map(|arg| arg.to_string()))
+   }
+
+   // Primera línea del error, sin la ayuda que se agrega a algunos
+   fn error(args: &[&str]) -> String {
+      let message = parse(args).err().expect("se esperaba un error");
+      message.lines().next().unwrap_or_default().to_string()
+   }
+
+   #[test]
+   fn valid_arguments() {
+      let options = parse(&["--width", "320", "--msaa", "4", "--threads", "2", "--output", "nave.PNG"]).unwrap().unwrap();
+      assert_eq!((options.width, options.threads, options.msaa), (320, 2, Msaa::X4));
+      assert_eq!(options.output.as_deref(), Some(Path::new("nave.PNG")));
+      assert!(parse(&["--model", "otra.obj", "-h"]).unwrap().is_none());
+   }
+
+   #[test]
+   fn unknown_flags_and_missing_values() {
+      assert_eq!(error(&["--widht", "320"]), "opción desconocida: --widht");
+      assert_eq!(error(&["--width", "320", "--height"]), "falta el valor de --height");
+   }
+
+   #[test]
+   fn out_of_range_values() {
+      assert_eq!(error(&["--msaa", "3"]), "--msaa debe ser 1, 2, 4 u 8, no '3'");
+      assert_eq!(error(&["--msaa", "muchas"]), "--msaa debe ser 1, 2, 4 u 8, no 'muchas'");
+      assert_eq!(error(&["--threads", "0"]), "--threads debe ser un entero positivo, no '0'");
+      assert_eq!(error(&["--width", "-800"]), "--width debe ser un entero positivo, no '-800'");
+      assert_eq!(error(&["--height", "0"]), "--height debe ser un entero positivo, no '0'");
+   }
+
+   #[test]
+   fn output_needs_a_known_extension() {
+      assert_eq!(error(&["--output", "nave.jpg"]), "--output debe terminar en .png o .ppm, no 'nave.jpg'");
+      assert_eq!(error(&["--output", "nave"]), "--output debe terminar en .png o .ppm, no 'nave'");
+   }
+}
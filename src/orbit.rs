@@ -0,0 +1,107 @@
+use glm::Vec3;
+use crate::camera::Camera;
+
+// Radianes que gira la cámara por cada pixel que se arrastra el mouse
+const ROTATE_SPEED: f32 = 0.008;
+// Factor de distancia por cada paso de la rueda
+const ZOOM_STEP: f32 = 0.85;
+const MIN_DISTANCE: f32 = 0.3;
+const MAX_DISTANCE: f32 = 50.0;
+// Límite de la inclinación: justo antes de mirar recto hacia arriba o abajo,
+// donde el vector "arriba" dejaría de estar definido
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+// Qué tan rápido la cámara alcanza su destino: cada segundo queda
+// exp(-DAMPING) de la distancia pendiente
+const DAMPING: f32 = 12.0;
+
+// Posición de la cámara alrededor de un punto
+#[derive(Debug, Clone, Copy)]
+struct Orbit {
+   target: Vec3,
+   // Giro alrededor del eje Y del mundo y elevación sobre el plano XZ
+   yaw: f32,
+   pitch: f32,
+   distance: f32,
+}
+
+impl Orbit {
+   fn eye(&self) -> Vec3 {
+      let direction = Vec3::new(
+         self.pitch.cos() * self.yaw.sin(),
+         self.pitch.sin(),
+         self.pitch.cos() * self.yaw.cos(),
+      );
+      self.target + direction * self.distance
+   }
+}
+
+// Control de la cámara con el mouse, al estilo del visor de Blender: arrastrar
+// gira alrededor del punto de interés, arrastrar con el botón del medio lo
+// desplaza y la rueda acerca o aleja. Los movimientos fijan un destino y la
+// cámara lo alcanza de forma suave en `update`.
+pub struct OrbitController {
+   current: Orbit,
+   goal: Orbit,
+   initial: Orbit,
+}
+
+impl OrbitController {
+   // Tomar la posición inicial de una cámara
+   pub fn new(camera: &Camera) -> Self {
+      let offset = camera.eye - camera.target;
+      let distance = glm::length(offset).max(MIN_DISTANCE);
+      let orbit = Orbit {
+         target: camera.target,
+         yaw: offset.x.atan2(offset.z),
+         pitch: (offset.y / distance).clamp(-1.0, 1.0).asin(),
+         distance,
+      };
+      OrbitController { current: orbit, goal: orbit, initial: orbit }
+   }
+
+   // Girar alrededor del punto de interés según el arrastre en pixeles
+   pub fn rotate(&mut self, dx: f32, dy: f32) {
+      self.goal.yaw -= dx * ROTATE_SPEED;
+      self.goal.pitch = (self.goal.pitch + dy * ROTATE_SPEED).clamp(-MAX_PITCH, MAX_PITCH);
+   }
+
+   // Desplazar el punto de interés en el plano de la pantalla para que lo que
+   // está a su distancia siga al mouse. `height` es el alto de la vista en
+   // pixeles y `fov` su campo de visión vertical.
+   pub fn pan(&mut self, dx: f32, dy: f32, height: usize, fov: f32) {
+      let forward = glm::normalize(self.goal.target - self.goal.eye());
+      let right = glm::normalize(glm::cross(forward, Vec3::new(0.0, 1.0, 0.0)));
+      let up = glm::cross(right, forward);
+      let units_per_pixel = 2.0 * self.goal.distance * (fov * 0.5).tan() / height.max(1) as f32;
+      self.goal.target = self.goal.target + (right * -dx + up * dy) * units_per_pixel;
+   }
+
+   // Acercar (pasos positivos) o alejar la cámara, como la rueda del mouse
+   pub fn zoom(&mut self, steps: f32) {
+      self.goal.distance = (self.goal.distance * ZOOM_STEP.powf(steps)).clamp(MIN_DISTANCE, MAX_DISTANCE);
+   }
+
+   // Volver a la posición inicial (también de forma suave)
+   pub fn reset(&mut self) {
+      self.goal = self.initial;
+   }
+
+   // Avanzar `dt` segundos hacia el destino
+   pub fn update(&mut self, dt: f32) {
+      let t = 1.0 - (-DAMPING * dt).exp();
+      let lerp = |a: f32, b: f32| a + (b - a) * t;
+      self.current = Orbit {
+         target: self.current.target + (self.goal.target - self.current.target) * t,
+         yaw: lerp(self.current.yaw, self.goal.yaw),
+         pitch: lerp(self.current.pitch, self.goal.pitch),
+         distance: lerp(self.current.distance, self.goal.distance),
+      };
+   }
+
+   // Colocar la cámara en la posición actual
+   pub fn apply(&self, camera: &mut Camera) {
+      camera.eye = self.current.eye();
+      camera.target = self.current.target;
+      camera.up = Vec3::new(0.0, 1.0, 0.0);
+   }
+}
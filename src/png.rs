@@ -0,0 +1,782 @@
+use std::io;
+use crate::color::Color;
+use crate::image::Image;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+fn invalid(message: &str) -> io::Error {
+   io::Error::new(io::ErrorKind::InvalidData, format!("PNG: {}", message))
+}
+
+// Decodificar un archivo PNG completo (sin entrelazado) a una imagen RGB.
+// Soporta escala de grises, RGB, paleta y sus variantes con alfa (el alfa se
+// descarta), con 1, 2, 4, 8 o 16 bits por canal.
+pub fn decode(data: &[u8]) -> io::Result<Image> {
+   if data.len() < 8 || data[..8] != SIGNATURE {
+      return Err(invalid("firma inválida"));
+   }
+
+   let mut header = None;
+   let mut palette: Vec<Color> = Vec::new();
+   let mut compressed = Vec::new();
+
+   // Recorrer los chunks: longitud, tipo, datos y CRC
+   let mut pos = 8;
+   while pos + 8 <= data.len() {
+      let length = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+      let kind = &data[pos + 4..pos + 8];
+      let start = pos + 8;
+      let end = start.checked_add(length).filter(|&end| end + 4 <= data.len())
+         .ok_or_else(|| invalid("chunk truncado"))?;
+      let body = &data[start..end];
+
+      match kind {
+         b"IHDR" => {
+            if body.len() != 13 {
+               return Err(invalid("IHDR inválido"));
+            }
+            let width = u32::from_be_bytes([body[0], body[1], body[2], body[3]]) as usize;
+            let height = u32::from_be_bytes([body[4], body[5], body[6], body[7]]) as usize;
+            header = Some((width, height, body[8], body[9], body[12]));
+         },
+         b"PLTE" => {
+            palette = body.chunks_exact(3).map(|c| Color::new(c[0], c[1], c[2])).collect();
+         },
+         b"IDAT" => compressed.extend_from_slice(body),
+         b"IEND" => break,
+         _ => {}
+      }
+
+      pos = end + 4;
+   }
+
+   let (width, height, bit_depth, color_type, interlace) = header.ok_or_else(|| invalid("falta IHDR"))?;
+   if interlace != 0 {
+      return Err(invalid("imágenes entrelazadas no soportadas"));
+   }
+
+   let channels = match color_type {
+      0 => 1, // Escala de grises
+      2 => 3, // RGB
+      3 => 1, // Paleta
+      4 => 2, // Grises + alfa
+      6 => 4, // RGBA
+      _ => return Err(invalid("tipo de color desconocido")),
+   };
+   let valid_depth = match color_type {
+      0 => matches!(bit_depth, 1 | 2 | 4 | 8 | 16),
+      3 => matches!(bit_depth, 1 | 2 | 4 | 8),
+      _ => matches!(bit_depth, 8 | 16),
+   };
+   if !valid_depth || width == 0 || height == 0 {
+      return Err(invalid("profundidad de bits o tamaño inválido"));
+   }
+
+   let raw = zlib_decompress(&compressed)?;
+
+   // Bytes por fila y distancia (en bytes) al pixel anterior para los filtros
+   let bits_per_pixel = channels * bit_depth as usize;
+   let bpp = bits_per_pixel.div_ceil(8);
+   // El tamaño sale del encabezado, así que puede no caber en un usize
+   let too_large = || invalid("imagen demasiado grande");
+   let stride = width.checked_mul(bits_per_pixel).ok_or_else(too_large)?.div_ceil(8);
+   let size = (stride + 1).checked_mul(height).ok_or_else(too_large)?;
+   if raw.len() < size {
+      return Err(invalid("datos de imagen incompletos"));
+   }
+
+   let mut pixels = Vec::with_capacity(width * height);
+   let mut previous = vec![0u8; stride];
+   let mut current = vec![0u8; stride];
+
+   for y in 0..height {
+      let line = &raw[y * (stride + 1)..(y + 1) * (stride + 1)];
+      current.copy_from_slice(&line[1..]);
+      unfilter(line[0], &mut current, &previous, bpp)?;
+
+      for x in 0..width {
+         let sample = |channel: usize| read_sample(&current, x * channels + channel, bit_depth);
+         let max = ((1u32 << bit_depth) - 1) as f32;
+         let to_u8 = |v: u16| (v as f32 * 255.0 / max + 0.5) as u8;
+
+         let color = match color_type {
+            0 | 4 => {
+               let g = to_u8(sample(0));
+               Color::new(g, g, g)
+            },
+            3 => *palette.get(sample(0) as usize).ok_or_else(|| invalid("índice de paleta fuera de rango"))?,
+            _ => Color::new(to_u8(sample(0)), to_u8(sample(1)), to_u8(sample(2))),
+         };
+         pixels.push(color);
+      }
+
+      std::mem::swap(&mut previous, &mut current);
+   }
+
+   Ok(Image { width, height, pixels })
+}
+
+// Leer la muestra número `index` de una fila ya sin filtro
+fn read_sample(row: &[u8], index: usize, bit_depth: u8) -> u16 {
+   match bit_depth {
+      16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+      8 => row[index] as u16,
+      _ => {
+         let bit = index * bit_depth as usize;
+         let shift = 8 - bit_depth as usize - bit % 8;
+         ((row[bit / 8] >> shift) & ((1 << bit_depth) - 1)) as u16
+      },
+   }
+}
+
+// Deshacer el filtro de una fila (None, Sub, Up, Average, Paeth)
+fn unfilter(filter: u8, row: &mut [u8], previous: &[u8], bpp: usize) -> io::Result<()> {
+   for i in 0..row.len() {
+      let a = if i >= bpp { row[i - bpp] } else { 0 };
+      let b = previous[i];
+      let c = if i >= bpp { previous[i - bpp] } else { 0 };
+
+      let predictor = match filter {
+         0 => 0,
+         1 => a,
+         2 => b,
+         3 => ((a as u16 + b as u16) / 2) as u8,
+         4 => paeth(a, b, c),
+         _ => return Err(invalid("filtro desconocido")),
+      };
+      row[i] = row[i].wrapping_add(predictor);
+   }
+   Ok(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+   let p = a as i16 + b as i16 - c as i16;
+   let pa = (p - a as i16).abs();
+   let pb = (p - b as i16).abs();
+   let pc = (p - c as i16).abs();
+   if pa <= pb && pa <= pc {
+      a
+   } else if pb <= pc {
+      b
+   } else {
+      c
+   }
+}
+
+// ---------------------------------------------------------------------------
+// Descompresión zlib / DEFLATE (RFC 1950 y RFC 1951)
+// ---------------------------------------------------------------------------
+
+pub fn zlib_decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+   if data.len() < 2 {
+      return Err(invalid("flujo zlib vacío"));
+   }
+   let cmf = data[0];
+   let flg = data[1];
+   if cmf & 0x0f != 8 || !(((cmf as u16) << 8) | flg as u16).is_multiple_of(31) {
+      return Err(invalid("encabezado zlib inválido"));
+   }
+   if flg & 0x20 != 0 {
+      return Err(invalid("diccionario zlib no soportado"));
+   }
+   inflate(&data[2..])
+}
+
+// Lector de bits en el orden de DEFLATE (el bit menos significativo primero)
+struct BitReader<'a> {
+   data: &'a [u8],
+   pos: usize,
+   bit: u32,
+}
+
+impl<'a> BitReader<'a> {
+   fn new(data: &'a [u8]) -> Self {
+      BitReader { data, pos: 0, bit: 0 }
+   }
+
+   fn read_bit(&mut self) -> io::Result<u32> {
+      let byte = *self.data.get(self.pos).ok_or_else(|| invalid("flujo DEFLATE truncado"))?;
+      let value = (byte >> self.bit) & 1;
+      self.bit += 1;
+      if self.bit == 8 {
+         self.bit = 0;
+         self.pos += 1;
+      }
+      Ok(value as u32)
+   }
+
+   fn read_bits(&mut self, count: u32) -> io::Result<u32> {
+      let mut value = 0;
+      for i in 0..count {
+         value |= self.read_bit()? << i;
+      }
+      Ok(value)
+   }
+
+   fn align_to_byte(&mut self) {
+      if self.bit != 0 {
+         self.bit = 0;
+         self.pos += 1;
+      }
+   }
+}
+
+// Código de Huffman canónico: cuántos códigos hay de cada longitud y los
+// símbolos ordenados por código
+struct Huffman {
+   counts: [u16; 16],
+   symbols: Vec<u16>,
+}
+
+impl Huffman {
+   fn new(lengths: &[u8]) -> Self {
+      let mut counts = [0u16; 16];
+      for &length in lengths {
+         counts[length as usize] += 1;
+      }
+      counts[0] = 0;
+
+      let mut offsets = [0u16; 16];
+      for i in 1..16 {
+         offsets[i] = offsets[i - 1] + counts[i - 1];
+      }
+
+      let mut symbols = vec![0u16; lengths.len()];
+      for (symbol, &length) in lengths.iter().enumerate() {
+         if length != 0 {
+            symbols[offsets[length as usize] as usize] = symbol as u16;
+            offsets[length as usize] += 1;
+         }
+      }
+
+      Huffman { counts, symbols }
+   }
+
+   fn decode(&self, reader: &mut BitReader) -> io::Result<u16> {
+      let mut code: i32 = 0;
+      let mut first: i32 = 0;
+      let mut index: i32 = 0;
+      for length in 1..16 {
+         code |= reader.read_bit()? as i32;
+         let count = self.counts[length] as i32;
+         if code - first < count {
+            return Ok(self.symbols[(index + code - first) as usize]);
+         }
+         index += count;
+         first = (first + count) << 1;
+         code <<= 1;
+      }
+      Err(invalid("código de Huffman inválido"))
+   }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+   3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+   35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+   0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+   3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+   1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+   257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+   0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+   7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+// Orden en que se transmiten las longitudes del código de longitudes
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+pub fn inflate(data: &[u8]) -> io::Result<Vec<u8>> {
+   let mut reader = BitReader::new(data);
+   let mut out = Vec::new();
+
+   loop {
+      let last = reader.read_bit()?;
+      let kind = reader.read_bits(2)?;
+
+      match kind {
+         // Bloque sin comprimir
+         0 => {
+            reader.align_to_byte();
+            let pos = reader.pos;
+            if pos + 4 > data.len() {
+               return Err(invalid("bloque sin comprimir truncado"));
+            }
+            let len = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
+            let nlen = u16::from_le_bytes([data[pos + 2], data[pos + 3]]) as usize;
+            if len != !nlen & 0xffff || pos + 4 + len > data.len() {
+               return Err(invalid("bloque sin comprimir inválido"));
+            }
+            out.extend_from_slice(&data[pos + 4..pos + 4 + len]);
+            reader.pos = pos + 4 + len;
+         },
+         // Huffman fijo
+         1 => {
+            let mut lengths = [0u8; 288];
+            lengths[..144].fill(8);
+            lengths[144..256].fill(9);
+            lengths[256..280].fill(7);
+            lengths[280..].fill(8);
+            let literals = Huffman::new(&lengths);
+            let distances = Huffman::new(&[5u8; 30]);
+            inflate_block(&mut reader, &mut out, &literals, &distances)?;
+         },
+         // Huffman dinámico
+         2 => {
+            let (literals, distances) = read_dynamic_tables(&mut reader)?;
+            inflate_block(&mut reader, &mut out, &literals, &distances)?;
+         },
+         _ => return Err(invalid("tipo de bloque inválido")),
+      }
+
+      if last == 1 {
+         break;
+      }
+   }
+
+   Ok(out)
+}
+
+fn read_dynamic_tables(reader: &mut BitReader) -> io::Result<(Huffman, Huffman)> {
+   let literal_count = reader.read_bits(5)? as usize + 257;
+   let distance_count = reader.read_bits(5)? as usize + 1;
+   let code_count = reader.read_bits(4)? as usize + 4;
+
+   let mut code_lengths = [0u8; 19];
+   for &index in CODE_LENGTH_ORDER.iter().take(code_count) {
+      code_lengths[index] = reader.read_bits(3)? as u8;
+   }
+   let code_huffman = Huffman::new(&code_lengths);
+
+   // Las longitudes de ambos códigos vienen comprimidas con repeticiones
+   let mut lengths = Vec::with_capacity(literal_count + distance_count);
+   while lengths.len() < literal_count + distance_count {
+      let symbol = code_huffman.decode(reader)?;
+      match symbol {
+         0..=15 => lengths.push(symbol as u8),
+         16 => {
+            let previous = *lengths.last().ok_or_else(|| invalid("repetición sin longitud previa"))?;
+            let repeat = 3 + reader.read_bits(2)?;
+            lengths.extend(std::iter::repeat_n(previous, repeat as usize));
+         },
+         17 => {
+            let repeat = 3 + reader.read_bits(3)?;
+            lengths.extend(std::iter::repeat_n(0, repeat as usize));
+         },
+         _ => {
+            let repeat = 11 + reader.read_bits(7)?;
+            lengths.extend(std::iter::repeat_n(0, repeat as usize));
+         },
+      }
+   }
+   if lengths.len() > literal_count + distance_count {
+      return Err(invalid("demasiadas longitudes de código"));
+   }
+
+   Ok((
+      Huffman::new(&lengths[..literal_count]),
+      Huffman::new(&lengths[literal_count..]),
+   ))
+}
+
+fn inflate_block(reader: &mut BitReader, out: &mut Vec<u8>, literals: &Huffman, distances: &Huffman) -> io::Result<()> {
+   loop {
+      let symbol = literals.decode(reader)? as usize;
+      match symbol {
+         0..=255 => out.push(symbol as u8),
+         256 => return Ok(()),
+         _ => {
+            // Copia de una secuencia anterior: longitud y distancia
+            let index = symbol - 257;
+            if index >= LENGTH_BASE.len() {
+               return Err(invalid("longitud inválida"));
+            }
+            let length = LENGTH_BASE[index] as usize + reader.read_bits(LENGTH_EXTRA[index] as u32)? as usize;
+
+            let dist_symbol = distances.decode(reader)? as usize;
+            if dist_symbol >= DIST_BASE.len() {
+               return Err(invalid("distancia inválida"));
+            }
+            let distance = DIST_BASE[dist_symbol] as usize + reader.read_bits(DIST_EXTRA[dist_symbol] as u32)? as usize;
+            if distance > out.len() {
+               return Err(invalid("distancia fuera de rango"));
+            }
+
+            let start = out.len() - distance;
+            for i in 0..length {
+               out.push(out[start + i]);
+            }
+         },
+      }
+   }
+}
+
+// ---------------------------------------------------------------------------
+// Codificación PNG y compresión zlib / DEFLATE
+// ---------------------------------------------------------------------------
+
+// Codificar una imagen como PNG RGB de 8 bits. Cada fila usa el filtro que
+// deja los valores más cercanos a cero, lo que ayuda a la compresión.
+pub fn encode(image: &Image) -> Vec<u8> {
+   let stride = image.width * 3;
+   let mut raw = Vec::with_capacity((stride + 1) * image.height);
+   let mut previous = vec![0u8; stride];
+   let mut row = Vec::with_capacity(stride);
+   let mut filtered = vec![0u8; stride];
+   let mut best = vec![0u8; stride];
+
+   for y in 0..image.height {
+      row.clear();
+      for pixel in &image.pixels[y * image.width..(y + 1) * image.width] {
+         row.extend_from_slice(&[pixel.r, pixel.g, pixel.b]);
+      }
+
+      let mut best_filter = 0;
+      let mut best_score = u64::MAX;
+      for filter in 0..5 {
+         apply_filter(filter, &row, &previous, 3, &mut filtered);
+         // Suma de los valores tomados como enteros con signo
+         let score = filtered.iter().map(|&b| (b as i8).unsigned_abs() as u64).sum();
+         if score < best_score {
+            best_score = score;
+            best_filter = filter;
+            best.copy_from_slice(&filtered);
+         }
+      }
+
+      raw.push(best_filter);
+      raw.extend_from_slice(&best);
+      previous.copy_from_slice(&row);
+   }
+
+   let mut header = Vec::with_capacity(13);
+   header.extend_from_slice(&(image.width as u32).to_be_bytes());
+   header.extend_from_slice(&(image.height as u32).to_be_bytes());
+   header.extend_from_slice(&[8, 2, 0, 0, 0]); // 8 bits, RGB, sin entrelazado
+
+   let mut out = SIGNATURE.to_vec();
+   write_chunk(&mut out, b"IHDR", &header);
+   write_chunk(&mut out, b"IDAT", &zlib_compress(&raw));
+   write_chunk(&mut out, b"IEND", &[]);
+   out
+}
+
+// Inverso de `unfilter`
+fn apply_filter(filter: u8, row: &[u8], previous: &[u8], bpp: usize, out: &mut [u8]) {
+   for i in 0..row.len() {
+      let left = if i >= bpp { row[i - bpp] } else { 0 };
+      let up = previous[i];
+      let up_left = if i >= bpp { previous[i - bpp] } else { 0 };
+      let predicted = match filter {
+         0 => 0,
+         1 => left,
+         2 => up,
+         3 => ((left as u16 + up as u16) / 2) as u8,
+         _ => paeth(left, up, up_left),
+      };
+      out[i] = row[i].wrapping_sub(predicted);
+   }
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+   out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+   let start = out.len();
+   out.extend_from_slice(kind);
+   out.extend_from_slice(body);
+   let crc = crc32(&out[start..]);
+   out.extend_from_slice(&crc.to_be_bytes());
+}
+
+const CRC_TABLE: [u32; 256] = {
+   let mut table = [0u32; 256];
+   let mut n = 0;
+   while n < 256 {
+      let mut c = n as u32;
+      let mut k = 0;
+      while k < 8 {
+         c = if c & 1 != 0 { 0xedb88320 ^ (c >> 1) } else { c >> 1 };
+         k += 1;
+      }
+      table[n] = c;
+      n += 1;
+   }
+   table
+};
+
+fn crc32(data: &[u8]) -> u32 {
+   let mut crc = 0xffffffffu32;
+   for &byte in data {
+      crc = CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+   }
+   crc ^ 0xffffffff
+}
+
+fn adler32(data: &[u8]) -> u32 {
+   let mut a = 1u32;
+   let mut b = 0u32;
+   for chunk in data.chunks(5552) {
+      for &byte in chunk {
+         a += byte as u32;
+         b += a;
+      }
+      a %= 65521;
+      b %= 65521;
+   }
+   (b << 16) | a
+}
+
+// Escritor de bits en el orden de DEFLATE (el bit menos significativo primero)
+struct BitWriter {
+   out: Vec<u8>,
+   buffer: u32,
+   count: u32,
+}
+
+impl BitWriter {
+   fn new() -> Self {
+      BitWriter { out: Vec::new(), buffer: 0, count: 0 }
+   }
+
+   fn write_bits(&mut self, value: u32, count: u32) {
+      self.buffer |= value << self.count;
+      self.count += count;
+      while self.count >= 8 {
+         self.out.push(self.buffer as u8);
+         self.buffer >>= 8;
+         self.count -= 8;
+      }
+   }
+
+   // Los códigos de Huffman se escriben empezando por el bit más significativo
+   fn write_code(&mut self, code: u32, length: u32) {
+      let reversed = code.reverse_bits() >> (32 - length);
+      self.write_bits(reversed, length);
+   }
+
+   fn finish(mut self) -> Vec<u8> {
+      if self.count > 0 {
+         self.out.push(self.buffer as u8);
+      }
+      self.out
+   }
+}
+
+// Código fijo de DEFLATE para un símbolo de literal/longitud
+fn write_fixed_literal(writer: &mut BitWriter, symbol: usize) {
+   let symbol = symbol as u32;
+   match symbol {
+      0..=143 => writer.write_code(0x30 + symbol, 8),
+      144..=255 => writer.write_code(0x190 + symbol - 144, 9),
+      256..=279 => writer.write_code(symbol - 256, 7),
+      _ => writer.write_code(0xc0 + symbol - 280, 8),
+   }
+}
+
+const WINDOW_SIZE: usize = 32768;
+const HASH_BITS: u32 = 15;
+const MAX_CHAIN: usize = 64;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+// Hash de los tres bytes que empiezan en `i`
+fn hash3(data: &[u8], i: usize) -> usize {
+   let value = (data[i] as u32) << 16 | (data[i + 1] as u32) << 8 | data[i + 2] as u32;
+   (value.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+}
+
+// Registrar la posición `i` al principio de la cadena de su hash
+fn insert_hash(data: &[u8], i: usize, head: &mut [usize], previous: &mut [usize]) {
+   if i + MIN_MATCH <= data.len() {
+      let h = hash3(data, i);
+      previous[i % WINDOW_SIZE] = head[h];
+      head[h] = i;
+   }
+}
+
+// Comprimir con un único bloque de Huffman fijo. Las repeticiones se buscan
+// con cadenas de hash sobre los últimos 32 KB, tomando la coincidencia más
+// larga que se encuentre (LZ77 voraz).
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+   let mut writer = BitWriter::new();
+   writer.write_bits(1, 1); // Último bloque
+   writer.write_bits(1, 2); // Huffman fijo
+
+   let mut head = vec![usize::MAX; 1 << HASH_BITS];
+   let mut previous = vec![usize::MAX; WINDOW_SIZE];
+
+   let mut pos = 0;
+   while pos < data.len() {
+      // Buscar la coincidencia más larga en la cadena de este hash
+      let mut best_length = 0;
+      let mut best_distance = 0;
+      if pos + MIN_MATCH <= data.len() {
+         let max_length = MAX_MATCH.min(data.len() - pos);
+         let mut candidate = head[hash3(data, pos)];
+         let mut chain = 0;
+         while candidate != usize::MAX && pos - candidate <= WINDOW_SIZE && chain < MAX_CHAIN {
+            let length = data[candidate..].iter()
+               .zip(&data[pos..pos + max_length])
+               .take_while(|(a, b)| a == b)
+               .count();
+            if length > best_length {
+               best_length = length;
+               best_distance = pos - candidate;
+               if length == max_length {
+                  break;
+               }
+            }
+            let next = previous[candidate % WINDOW_SIZE];
+            if next == usize::MAX || next >= candidate {
+               break;
+            }
+            candidate = next;
+            chain += 1;
+         }
+      }
+
+      if best_length >= MIN_MATCH {
+         let index = LENGTH_BASE.iter().rposition(|&base| base as usize <= best_length).unwrap();
+         write_fixed_literal(&mut writer, 257 + index);
+         writer.write_bits((best_length - LENGTH_BASE[index] as usize) as u32, LENGTH_EXTRA[index] as u32);
+
+         let index = DIST_BASE.iter().rposition(|&base| base as usize <= best_distance).unwrap();
+         writer.write_code(index as u32, 5);
+         writer.write_bits((best_distance - DIST_BASE[index] as usize) as u32, DIST_EXTRA[index] as u32);
+
+         for i in pos..pos + best_length {
+            insert_hash(data, i, &mut head, &mut previous);
+         }
+         pos += best_length;
+      } else {
+         write_fixed_literal(&mut writer, data[pos] as usize);
+         insert_hash(data, pos, &mut head, &mut previous);
+         pos += 1;
+      }
+   }
+
+   write_fixed_literal(&mut writer, 256); // Fin de bloque
+   writer.finish()
+}
+
+pub fn zlib_compress(data: &[u8]) -> Vec<u8> {
+   // Método 8 (DEFLATE) con ventana de 32 KB; el encabezado es múltiplo de 31
+   let mut out = vec![0x78, 0x01];
+   out.extend(deflate(data));
+   out.extend_from_slice(&adler32(data).to_be_bytes());
+   out
+}
+
+
+#[cfg(test)]
+mod tests {
+   use std::io;
+   use crate::color::Color;
+   use super::{adler32, decode, write_chunk, zlib_compress, zlib_decompress, SIGNATURE};
+
+   // Texto comprimido con zlib (nivel 9), que eligió un bloque con código de
+   // Huffman dinámico
+   const DYNAMIC_TEXT: &[u8] = b"aaba d caa accadbaabaaaacacaad abaadbacaababcabb ca accabbadaacb a abcaaaaaacbbdbacaaca bac bcbabdaaaaabaaaadababaabbaaa";
+   const DYNAMIC_ZLIB: [u8; 66] = [
+      0x78, 0xda, 0x25, 0x8c, 0xc1, 0x11, 0x00, 0x30, 0x08, 0xc2, 0x56, 0x71, 0xb5, 0x00,
+      0xfb, 0xcf, 0x50, 0xb4, 0xfa, 0xf0, 0x80, 0x08, 0x88, 0xc9, 0x18, 0x06, 0x9b, 0x88,
+      0x1a, 0x1d, 0x77, 0xc9, 0xac, 0xa8, 0xe7, 0x75, 0x65, 0xa4, 0x92, 0x07, 0x4a, 0xa4,
+      0x94, 0xa6, 0xb2, 0xc1, 0x8d, 0xa5, 0xcf, 0x96, 0xe9, 0x1d, 0xb9, 0x4f, 0xb9, 0xe8,
+      0x3a, 0xb3, 0x25, 0xec, 0x2b, 0x3c, 0x17, 0xb9, 0x2b, 0x42,
+   ];
+
+   // zlib con los datos partidos en dos bloques sin comprimir
+   fn stored_zlib(data: &[u8]) -> Vec<u8> {
+      let mut out = vec![0x78, 0x01];
+      let (first, second) = data.split_at(data.len() / 2);
+      for (last, block) in [(0, first), (1, second)] {
+         let length = block.len() as u16;
+         out.push(last);
+         out.extend_from_slice(&length.to_le_bytes());
+         out.extend_from_slice(&(!length).to_le_bytes());
+         out.extend_from_slice(block);
+      }
+      out.extend_from_slice(&adler32(data).to_be_bytes());
+      out
+   }
+
+   // PNG con el encabezado dado, los chunks extra antes de los datos y las
+   // filas ya filtradas (cada una empieza con su byte de filtro)
+   fn png(width: u32, height: u32, bit_depth: u8, color_type: u8, chunks: &[(&[u8; 4], &[u8])], idat: &[u8]) -> Vec<u8> {
+      let mut header = Vec::new();
+      header.extend_from_slice(&width.to_be_bytes());
+      header.extend_from_slice(&height.to_be_bytes());
+      header.extend_from_slice(&[bit_depth, color_type, 0, 0, 0]);
+
+      let mut out = SIGNATURE.to_vec();
+      write_chunk(&mut out, b"IHDR", &header);
+      for (kind, body) in chunks {
+         write_chunk(&mut out, kind, body);
+      }
+      write_chunk(&mut out, b"IDAT", idat);
+      write_chunk(&mut out, b"IEND", &[]);
+      out
+   }
+
+   fn gray(value: u8) -> Color {
+      Color::new(value, value, value)
+   }
+
+   #[test]
+   fn inflate_dynamic_huffman() {
+      assert_eq!((DYNAMIC_ZLIB[2] >> 1) & 3, 2, "el bloque debe usar Huffman dinámico");
+      assert_eq!(zlib_decompress(&DYNAMIC_ZLIB).unwrap(), DYNAMIC_TEXT);
+   }
+
+   #[test]
+   fn inflate_stored_blocks() {
+      // Escala de grises de 8 bits guardada sin comprimir
+      let raw = [0, 0, 128, 255, 0, 10, 20, 30];
+      let image = decode(&png(3, 2, 8, 0, &[], &stored_zlib(&raw))).unwrap();
+      assert_eq!(image.pixels, [gray(0), gray(128), gray(255), gray(10), gray(20), gray(30)]);
+   }
+
+   #[test]
+   fn palette_with_transparency() {
+      // Índices de 2 bits: 0 1 2 en la primera fila y 3 2 1 en la segunda. El
+      // alfa de tRNS se descarta.
+      let palette = [255, 0, 0, 0, 255, 0, 0, 0, 255, 40, 50, 60];
+      let alpha = [0, 128];
+      let raw = [0, 0b0001_1000, 0, 0b1110_0100];
+      let data = png(3, 2, 2, 3, &[(b"PLTE", &palette), (b"tRNS", &alpha)], &zlib_compress(&raw));
+      let image = decode(&data).unwrap();
+      let colors = [Color::new(255, 0, 0), Color::new(0, 255, 0), Color::new(0, 0, 255), Color::new(40, 50, 60)];
+      assert_eq!(image.pixels, [colors[0], colors[1], colors[2], colors[3], colors[2], colors[1]]);
+   }
+
+   #[test]
+   fn grayscale_sub_byte_depths() {
+      // 1 bit: 10 pixeles, la fila termina a mitad del segundo byte
+      let raw = [0, 0b1011_0011, 0b1000_0000];
+      let image = decode(&png(10, 1, 1, 0, &[], &zlib_compress(&raw))).unwrap();
+      let expected: Vec<Color> = [1, 0, 1, 1, 0, 0, 1, 1, 1, 0].iter().map(|&b| gray(b * 255)).collect();
+      assert_eq!(image.pixels, expected);
+
+      // 4 bits: 0, 8 y 15 se escalan a 0..255
+      let raw = [0, 0x08, 0xf0];
+      let image = decode(&png(3, 1, 4, 0, &[], &zlib_compress(&raw))).unwrap();
+      assert_eq!(image.pixels, [gray(0), gray(136), gray(255)]);
+   }
+
+   #[test]
+   fn sixteen_bit_channels() {
+      let raw = [0, 0xff, 0xff, 0x80, 0x00, 0x00, 0x00, 0x01, 0x01, 0x12, 0x34, 0xfe, 0xff];
+      let image = decode(&png(2, 1, 16, 2, &[], &zlib_compress(&raw))).unwrap();
+      assert_eq!(image.pixels, [Color::new(255, 128, 0), Color::new(1, 18, 254)]);
+   }
+
+   #[test]
+   fn huge_dimensions_are_rejected() {
+      let data = png(u32::MAX, u32::MAX, 16, 6, &[], &zlib_compress(&[0]));
+      let error = decode(&data).err().expect("se esperaba un error");
+      assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+   }
+}
@@ -0,0 +1,457 @@
+use std::io;
+use std::ops::{Add, Mul};
+use std::path::Path;
+use glm::Vec3;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::tiles::default_threads;
+
+// Umbrales de FXAA (los valores de calidad de FXAA 3.11): los pixeles con
+// menos contraste que esto se dejan como están
+const FXAA_CONTRAST_THRESHOLD: f32 = 0.0312;
+const FXAA_RELATIVE_THRESHOLD: f32 = 0.063;
+// Cuánto se suavizan los detalles de un solo pixel
+const FXAA_SUBPIXEL_BLENDING: f32 = 0.75;
+// Pasos (en pixeles) con que se busca dónde termina un borde, y cuánto más
+// se supone que sigue si no se encontró el final
+const FXAA_EDGE_STEPS: [f32; 10] = [1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 2.0, 2.0, 2.0, 4.0];
+const FXAA_EDGE_GUESS: f32 = 8.0;
+
+// Peso de cada canal en el brillo percibido
+const LUMA: Vec3 = Vec3 { x: 0.299, y: 0.587, z: 0.114 };
+
+// Efecto que se aplica sobre la imagen ya renderizada
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostEffect {
+   // Fast Approximate Anti-Aliasing: busca los bordes por el contraste y
+   // mezcla cada pixel con su vecino al otro lado del borde
+   Fxaa,
+   // Realza los bordes sumando la diferencia con los cuatro vecinos
+   Sharpen { amount: f32 },
+   // Oscurece hacia las esquinas. `radius` es donde empieza, como fracción
+   // de la distancia del centro a una esquina.
+   Vignette { strength: f32, radius: f32 },
+   // Separa el rojo y el azul hacia los bordes como un lente barato; `shift`
+   // es el desplazamiento en pixeles en las esquinas
+   ChromaticAberration { shift: f32 },
+   // Corrección de color con una tabla 3D
+   ColorGrading(Lut),
+}
+
+impl PostEffect {
+   pub fn name(&self) -> &'static str {
+      match self {
+         PostEffect::Fxaa => "FXAA",
+         PostEffect::Sharpen { .. } => "nitidez",
+         PostEffect::Vignette { .. } => "viñeta",
+         PostEffect::ChromaticAberration { .. } => "aberración cromática",
+         PostEffect::ColorGrading(_) => "gradación de color",
+      }
+   }
+
+   // Nombre en la línea de comandos
+   pub fn id(&self) -> &'static str {
+      match self {
+         PostEffect::Fxaa => "fxaa",
+         PostEffect::Sharpen { .. } => "sharpen",
+         PostEffect::Vignette { .. } => "vignette",
+         PostEffect::ChromaticAberration { .. } => "chromatic",
+         PostEffect::ColorGrading(_) => "grade",
+      }
+   }
+
+   // Efecto con sus parámetros por defecto a partir de su nombre en la línea
+   // de comandos
+   pub fn from_id(id: &str) -> Option<Self> {
+      match id {
+         "fxaa" => Some(PostEffect::Fxaa),
+         "sharpen" => Some(PostEffect::Sharpen { amount: 0.5 }),
+         "vignette" => Some(PostEffect::Vignette { strength: 0.6, radius: 0.4 }),
+         "chromatic" => Some(PostEffect::ChromaticAberration { shift: 2.0 }),
+         "grade" => Some(PostEffect::ColorGrading(Lut::cinematic())),
+         _ => None,
+      }
+   }
+}
+
+// Un efecto de la cadena, que se puede apagar sin sacarlo de su lugar
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostPass {
+   pub effect: PostEffect,
+   pub enabled: bool,
+}
+
+// Efectos que se aplican en orden sobre el framebuffer después de `render`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostChain {
+   pub passes: Vec<PostPass>,
+   // Hilos entre los que se reparten las filas de la imagen
+   pub threads: usize,
+}
+
+impl Default for PostChain {
+   fn default() -> Self {
+      PostChain { passes: Vec::new(), threads: default_threads() }
+   }
+}
+
+impl PostChain {
+   // Todos los efectos con sus parámetros por defecto, apagados, en un orden
+   // razonable: primero el color y el suavizado, al final lo que imita al lente
+   pub fn standard() -> Self {
+      let passes = ["grade", "fxaa", "sharpen", "chromatic", "vignette"]
+         .into_iter()
+         .filter_map(PostEffect::from_id)
+         .map(|effect| PostPass { effect, enabled: false })
+         .collect();
+      PostChain { passes, ..PostChain::default() }
+   }
+
+   // Si hay algún efecto encendido
+   pub fn is_active(&self) -> bool {
+      self.passes.iter().any(|pass| pass.enabled)
+   }
+
+   // Pasar un efecto un lugar antes en la cadena. Devuelve su nueva posición.
+   pub fn move_earlier(&mut self, index: usize) -> usize {
+      if index > 0 && index < self.passes.len() {
+         self.passes.swap(index - 1, index);
+         index - 1
+      } else {
+         index
+      }
+   }
+
+   // Cambiar la tabla de las gradaciones de color de la cadena
+   pub fn set_lut(&mut self, lut: Lut) {
+      for pass in &mut self.passes {
+         if let PostEffect::ColorGrading(table) = &mut pass.effect {
+            *table = lut.clone();
+         }
+      }
+   }
+
+   // Aplicar los efectos encendidos. Entre uno y otro la imagen se guarda con
+   // decimales, así que solo se redondea a 8 bits al final.
+   pub fn apply(&self, framebuffer: &mut Framebuffer) {
+      if !self.is_active() {
+         return;
+      }
+      let mut layer = Layer {
+         width: framebuffer.width,
+         height: framebuffer.height,
+         pixels: framebuffer.buffer.iter().map(|c| c.to_vec3()).collect(),
+      };
+      for pass in self.passes.iter().filter(|pass| pass.enabled) {
+         layer.pixels = layer.map(self.threads, |x, y| layer.shade(&pass.effect, x, y));
+      }
+      for (pixel, color) in framebuffer.buffer.iter_mut().zip(&layer.pixels) {
+         *pixel = Color::from_vec3(*color);
+      }
+   }
+}
+
+// Imagen de trabajo con componentes en [0, 1]. Dentro de este módulo los
+// centros de los pixeles están en coordenadas enteras.
+struct Layer {
+   width: usize,
+   height: usize,
+   pixels: Vec<Vec3>,
+}
+
+impl Layer {
+   // Pixel en (x, y); fuera de la imagen se repite el borde
+   fn at(&self, x: i32, y: i32) -> Vec3 {
+      let x = x.clamp(0, self.width as i32 - 1) as usize;
+      let y = y.clamp(0, self.height as i32 - 1) as usize;
+      self.pixels[y * self.width + x]
+   }
+
+   // Nueva imagen con el color que da `f` para cada pixel, calculada por
+   // bandas de filas en paralelo
+   fn map<F: Fn(usize, usize) -> Vec3 + Sync>(&self, threads: usize, f: F) -> Vec<Vec3> {
+      let mut pixels = vec![Vec3::new(0.0, 0.0, 0.0); self.width * self.height];
+      if pixels.is_empty() {
+         return pixels;
+      }
+      let band = self.height.div_ceil(threads.max(1)) * self.width;
+      std::thread::scope(|scope| {
+         for (i, chunk) in pixels.chunks_mut(band).enumerate() {
+            let f = &f;
+            scope.spawn(move || {
+               for (j, pixel) in chunk.iter_mut().enumerate() {
+                  let index = i * band + j;
+                  *pixel = f(index % self.width, index / self.width);
+               }
+            });
+         }
+      });
+      pixels
+   }
+
+   fn shade(&self, effect: &PostEffect, x: usize, y: usize) -> Vec3 {
+      let color = self.pixels[y * self.width + x];
+      // Posición respecto del centro, con 1 en las esquinas
+      let center = Vec3::new(self.width as f32 * 0.5, self.height as f32 * 0.5, 0.0);
+      let corner = glm::length(center);
+      let offset = (Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0) - center) / corner;
+
+      match effect {
+         PostEffect::Fxaa => self.fxaa(x as i32, y as i32),
+         PostEffect::Sharpen { amount } => {
+            let (x, y) = (x as i32, y as i32);
+            let neighbours = self.at(x, y - 1) + self.at(x, y + 1) + self.at(x - 1, y) + self.at(x + 1, y);
+            clamp01(color + (color * 4.0 - neighbours) * *amount)
+         },
+         PostEffect::Vignette { strength, radius } => {
+            let t = ((glm::length(offset) - radius) / (1.0 - radius).max(1e-6)).clamp(0.0, 1.0);
+            color * (1.0 - strength * t * t * (3.0 - 2.0 * t))
+         },
+         PostEffect::ChromaticAberration { shift } => {
+            let (dx, dy) = (offset.x * shift, offset.y * shift);
+            let red = bilinear(&self.pixels, self.width, self.height, x as f32 + dx, y as f32 + dy).x;
+            let blue = bilinear(&self.pixels, self.width, self.height, x as f32 - dx, y as f32 - dy).z;
+            Vec3::new(red, color.y, blue)
+         },
+         PostEffect::ColorGrading(lut) => lut.sample(color),
+      }
+   }
+
+   fn fxaa(&self, x: i32, y: i32) -> Vec3 {
+      let luma = |dx: i32, dy: i32| glm::dot(self.at(x + dx, y + dy), LUMA);
+      let m = luma(0, 0);
+      let (n, s, e, w) = (luma(0, -1), luma(0, 1), luma(1, 0), luma(-1, 0));
+      let (ne, nw, se, sw) = (luma(1, -1), luma(-1, -1), luma(1, 1), luma(-1, 1));
+
+      // Los pixeles sin contraste con sus vecinos no están en un borde
+      let highest = m.max(n).max(s).max(e).max(w);
+      let lowest = m.min(n).min(s).min(e).min(w);
+      let contrast = highest - lowest;
+      if contrast < FXAA_CONTRAST_THRESHOLD.max(FXAA_RELATIVE_THRESHOLD * highest) {
+         return self.at(x, y);
+      }
+
+      // Un pixel distinto de todo su entorno (un detalle más fino que un
+      // pixel) se mezcla según cuánto se aparta del promedio de los vecinos
+      let average = (2.0 * (n + s + e + w) + ne + nw + se + sw) / 12.0;
+      let filter = ((average - m).abs() / contrast).clamp(0.0, 1.0);
+      let smooth = filter * filter * (3.0 - 2.0 * filter);
+      let subpixel_blend = smooth * smooth * FXAA_SUBPIXEL_BLENDING;
+
+      // El borde es horizontal si el brillo cambia más hacia arriba y abajo
+      // que hacia los lados. Se mezcla hacia el vecino con el que más contrasta.
+      let horizontal = 2.0 * (n + s - 2.0 * m).abs() + (ne + se - 2.0 * e).abs() + (nw + sw - 2.0 * w).abs();
+      let vertical = 2.0 * (e + w - 2.0 * m).abs() + (ne + nw - 2.0 * n).abs() + (se + sw - 2.0 * s).abs();
+      let is_horizontal = horizontal >= vertical;
+      let (positive, negative) = if is_horizontal { (s, n) } else { (e, w) };
+      let (step, opposite) = if (positive - m).abs() >= (negative - m).abs() { (1.0, positive) } else { (-1.0, negative) };
+      let gradient = (opposite - m).abs();
+      // Dirección hacia el otro lado del borde y a lo largo de él
+      let (normal_x, normal_y) = if is_horizontal { (0.0, step) } else { (step, 0.0) };
+      let (along_x, along_y) = if is_horizontal { (1.0, 0.0) } else { (0.0, 1.0) };
+
+      // Recorrer el borde hacia los dos lados, a medio pixel del centro (donde
+      // el filtro bilineal promedia los dos lados), hasta que el brillo deja de
+      // parecerse al del borde
+      let edge_x = x as f32 + normal_x * 0.5;
+      let edge_y = y as f32 + normal_y * 0.5;
+      let edge_luma = (m + opposite) * 0.5;
+      let threshold = gradient * 0.25;
+      let luma_at = |px: f32, py: f32| glm::dot(bilinear(&self.pixels, self.width, self.height, px, py), LUMA);
+      let search = |direction: f32| {
+         let mut distance = 0.0;
+         let mut delta = 0.0;
+         for step in FXAA_EDGE_STEPS {
+            distance += step;
+            delta = luma_at(edge_x + along_x * direction * distance, edge_y + along_y * direction * distance) - edge_luma;
+            if delta.abs() >= threshold {
+               return (distance, delta);
+            }
+         }
+         (distance + FXAA_EDGE_GUESS, delta)
+      };
+      let (positive_distance, positive_delta) = search(1.0);
+      let (negative_distance, negative_delta) = search(-1.0);
+
+      // Cerca del extremo donde el borde sube o baja hacia este pixel la
+      // mezcla es mayor, como si el borde fuera una rampa entre los extremos
+      let (distance, delta) = if positive_distance <= negative_distance {
+         (positive_distance, positive_delta)
+      } else {
+         (negative_distance, negative_delta)
+      };
+      let edge_blend = if (delta >= 0.0) == (m - edge_luma >= 0.0) {
+         0.0
+      } else {
+         0.5 - distance / (positive_distance + negative_distance)
+      };
+
+      let blend = subpixel_blend.max(edge_blend);
+      bilinear(&self.pixels, self.width, self.height, x as f32 + normal_x * blend, y as f32 + normal_y * blend)
+   }
+}
+
+// Valor interpolado entre los cuatro pixeles más cercanos a (x, y)
+fn bilinear<T>(data: &[T], width: usize, height: usize, x: f32, y: f32) -> T
+where
+   T: Copy + Add<Output = T> + Mul<f32, Output = T>,
+{
+   let x = x.clamp(0.0, (width - 1) as f32);
+   let y = y.clamp(0.0, (height - 1) as f32);
+   let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+   let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+   let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+   let row = |y: usize| data[y * width + x0] * (1.0 - fx) + data[y * width + x1] * fx;
+   row(y0) * (1.0 - fy) + row(y1) * fy
+}
+
+fn clamp01(v: Vec3) -> Vec3 {
+   Vec3::new(v.x.clamp(0.0, 1.0), v.y.clamp(0.0, 1.0), v.z.clamp(0.0, 1.0))
+}
+
+// Muestras por canal mínimas de una LUT
+const MIN_LUT_SIZE: usize = 2;
+
+fn invalid(message: String) -> io::Error {
+   io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// Tabla 3D de colores: `size` muestras por canal, con el rojo variando más
+// rápido y después el verde (el orden de los archivos .cube). Para poder
+// interpolar hacen falta al menos dos muestras por canal; los campos son
+// privados para que eso y el largo de la tabla se comprueben una sola vez, al
+// crearla, y no en cada muestra.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lut {
+   size: usize,
+   table: Vec<Vec3>,
+}
+
+impl Lut {
+   // Tabla con el color que da `f` en cada muestra
+   pub fn from_fn<F: Fn(Vec3) -> Vec3>(size: usize, f: F) -> Self {
+      assert!(size >= MIN_LUT_SIZE, "una LUT necesita al menos {} muestras por canal, no {}", MIN_LUT_SIZE, size);
+      let max = (size - 1) as f32;
+      let table = (0..size * size * size)
+         .map(|i| f(Vec3::new((i % size) as f32 / max, (i / size % size) as f32 / max, (i / (size * size)) as f32 / max)))
+         .collect();
+      Lut { size, table }
+   }
+
+   // Muestras por canal
+   pub fn size(&self) -> usize {
+      self.size
+   }
+
+   // Tabla que deja los colores como están
+   pub fn identity(size: usize) -> Self {
+      Lut::from_fn(size, |c| c)
+   }
+
+   // Gradación de ejemplo: más contraste y saturación, sombras hacia el azul
+   // verdoso y luces hacia el naranja
+   pub fn cinematic() -> Self {
+      Lut::from_fn(17, |c| {
+         let curve = |v: f32| v + 0.35 * (v * v * (3.0 - 2.0 * v) - v);
+         let c = Vec3::new(curve(c.x), curve(c.y), curve(c.z));
+         let luma = glm::dot(c, LUMA);
+         let c = Vec3::new(luma, luma, luma) + (c - Vec3::new(luma, luma, luma)) * 1.15;
+         let shadows = Vec3::new(-0.03, 0.0, 0.04);
+         let highlights = Vec3::new(0.05, 0.015, -0.04);
+         clamp01(c + shadows * (1.0 - luma) + highlights * luma)
+      })
+   }
+
+   // Leer una tabla en formato .cube (Adobe / Resolve)
+   pub fn load_cube<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+      Lut::parse_cube(&std::fs::read_to_string(path)?)
+   }
+
+   pub fn parse_cube(text: &str) -> io::Result<Self> {
+      let mut size = 0;
+      let mut table = Vec::new();
+
+      for (number, line) in text.lines().enumerate() {
+         let parts: Vec<&str> = line.split_whitespace().collect();
+         let error = |message: &str| invalid(format!("LUT: {} en la línea {}", message, number + 1));
+         let numbers = |values: &[&str]| -> Option<Vec3> {
+            let values: Vec<f32> = values.iter().map(|v| v.parse().ok()).collect::<Option<_>>()?;
+            (values.len() == 3).then(|| Vec3::new(values[0], values[1], values[2]))
+         };
+
+         match parts.first().copied() {
+            None => {},
+            Some(first) if first.starts_with('#') => {},
+            Some("TITLE") => {},
+            Some("LUT_3D_SIZE") => {
+               size = parts.get(1)
+                  .and_then(|v| v.parse().ok())
+                  .filter(|n| (MIN_LUT_SIZE..=256).contains(n))
+                  .ok_or_else(|| error("tamaño inválido"))?;
+            },
+            Some("LUT_1D_SIZE") => return Err(error("las tablas 1D no están soportadas")),
+            // Solo se aceptan tablas para colores en [0, 1]
+            Some(keyword @ ("DOMAIN_MIN" | "DOMAIN_MAX")) => {
+               let expected = if keyword == "DOMAIN_MIN" { 0.0 } else { 1.0 };
+               if numbers(&parts[1..]) != Some(Vec3::new(expected, expected, expected)) {
+                  return Err(error("dominio distinto de [0, 1] no soportado"));
+               }
+            },
+            Some(_) => table.push(numbers(&parts).ok_or_else(|| error("color inválido"))?),
+         }
+      }
+
+      if size == 0 {
+         return Err(invalid("LUT: falta LUT_3D_SIZE".to_string()));
+      }
+      if table.len() != size * size * size {
+         return Err(invalid(format!("LUT: se esperaban {} colores y hay {}", size * size * size, table.len())));
+      }
+      Ok(Lut { size, table })
+   }
+
+   // Color de la tabla para `color`, interpolando entre las ocho muestras
+   // que lo rodean
+   pub fn sample(&self, color: Vec3) -> Vec3 {
+      let max = (self.size - 1) as f32;
+      let cell = |v: f32| {
+         let p = v.clamp(0.0, 1.0) * max;
+         let i = (p.floor() as usize).min(self.size - 2);
+         (i, p - i as f32)
+      };
+      let (r, fr) = cell(color.x);
+      let (g, fg) = cell(color.y);
+      let (b, fb) = cell(color.z);
+      let at = |r: usize, g: usize, b: usize| self.table[(b * self.size + g) * self.size + r];
+      let lerp = |a: Vec3, b: Vec3, t: f32| a + (b - a) * t;
+      let plane = |b: usize| lerp(lerp(at(r, g, b), at(r + 1, g, b), fr), lerp(at(r, g + 1, b), at(r + 1, g + 1, b), fr), fg);
+      lerp(plane(b), plane(b + 1), fb)
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use glm::Vec3;
+   use super::Lut;
+
+   #[test]
+   fn cube_lut_parse() {
+      // Tabla de 2x2x2 que invierte los colores, con el rojo variando más rápido
+      let text = "TITLE \"invertir\"\n# comentario\nLUT_3D_SIZE 2\nDOMAIN_MIN 0 0 0\n\n\
+         1 1 1\n0 1 1\n1 0 1\n0 0 1\n1 1 0\n0 1 0\n1 0 0\n0 0 0\n";
+      let lut = Lut::parse_cube(text).unwrap();
+      assert_eq!(lut.size(), 2);
+      let color = lut.sample(Vec3::new(0.25, 0.5, 1.0));
+      assert!(glm::length(color - Vec3::new(0.75, 0.5, 0.0)) < 1e-6, "{:?}", color);
+
+      assert!(Lut::parse_cube("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+      assert!(Lut::parse_cube("LUT_3D_SIZE 2\nDOMAIN_MAX 2 2 2\n").is_err());
+      assert!(Lut::parse_cube("LUT_3D_SIZE 1\n0 0 0\n").is_err());
+   }
+
+   #[test]
+   #[should_panic(expected = "al menos 2 muestras")]
+   fn lut_needs_two_samples() {
+      Lut::identity(1);
+   }
+}
@@ -0,0 +1,114 @@
+use std::time::{Duration, Instant};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::{Canvas, Texture, TextureCreator};
+use sdl2::video::{Window, WindowContext};
+use space_ship_render::framebuffer::Framebuffer;
+use crate::options::PresentFormat;
+
+impl PresentFormat {
+   pub fn name(&self) -> &'static str {
+      match self {
+         PresentFormat::Rgb24 => "RGB24",
+         PresentFormat::Rgba8888 => "RGBA8888",
+      }
+   }
+
+   fn bytes_per_pixel(&self) -> usize {
+      match self {
+         PresentFormat::Rgb24 => 3,
+         PresentFormat::Rgba8888 => 4,
+      }
+   }
+
+   fn sdl_format(&self) -> PixelFormatEnum {
+      match self {
+         PresentFormat::Rgb24 => PixelFormatEnum::RGB24,
+         PresentFormat::Rgba8888 => PixelFormatEnum::RGBA8888,
+      }
+   }
+}
+
+// Muestra el framebuffer en una ventana de SDL. La textura se crea una sola vez
+// y se reutiliza en cada cuadro (solo se vuelve a crear si cambia el tamaño del
+// framebuffer), y el contenido se sube con una única copia contigua.
+pub struct Presenter<'a> {
+   creator: &'a TextureCreator<WindowContext>,
+   texture: Texture<'a>,
+   format: PresentFormat,
+   width: usize,
+   height: usize,
+   // Pixeles empaquetados para RGBA8888; con RGB24 no hace falta
+   staging: Vec<u8>,
+   upload_time: Duration,
+   average_upload_time: Duration,
+}
+
+impl<'a> Presenter<'a> {
+   pub fn new(creator: &'a TextureCreator<WindowContext>, format: PresentFormat, width: usize, height: usize) -> Result<Self, String> {
+      let texture = create_texture(creator, format, width, height)?;
+      Ok(Presenter {
+         creator,
+         texture,
+         format,
+         width,
+         height,
+         staging: Vec::new(),
+         upload_time: Duration::ZERO,
+         average_upload_time: Duration::ZERO,
+      })
+   }
+
+   pub fn format(&self) -> PresentFormat {
+      self.format
+   }
+
+   // Promedio móvil del tiempo de subida (empaquetado incluido), más estable
+   // para mostrarlo que el del último cuadro
+   pub fn average_upload_time(&self) -> Duration {
+      self.average_upload_time
+   }
+
+   // Subir el framebuffer a la textura y mostrarla en toda la ventana
+   pub fn present(&mut self, canvas: &mut Canvas<Window>, framebuffer: &Framebuffer) -> Result<(), String> {
+      if framebuffer.width != self.width || framebuffer.height != self.height {
+         self.texture = create_texture(self.creator, self.format, framebuffer.width, framebuffer.height)?;
+         self.width = framebuffer.width;
+         self.height = framebuffer.height;
+      }
+
+      let start = Instant::now();
+      let pitch = self.width * self.format.bytes_per_pixel();
+      let pixels = match self.format {
+         PresentFormat::Rgb24 => framebuffer.as_bytes(),
+         PresentFormat::Rgba8888 => {
+            self.staging.clear();
+            self.staging.extend(framebuffer.buffer.iter().flat_map(|c| {
+               // Formato empaquetado: el orden de los bytes en memoria depende
+               // del procesador, por eso se arma el u32 completo
+               let packed = (c.r as u32) << 24 | (c.g as u32) << 16 | (c.b as u32) << 8 | 0xFF;
+               packed.to_ne_bytes()
+            }));
+            &self.staging
+         },
+      };
+      self.texture.update(None, pixels, pitch).map_err(|e| e.to_string())?;
+      self.upload_time = start.elapsed();
+
+      // Promedio exponencial: cada cuadro aporta un 5 %
+      self.average_upload_time = if self.average_upload_time.is_zero() {
+         self.upload_time
+      } else {
+         self.average_upload_time.mul_f32(0.95) + self.upload_time.mul_f32(0.05)
+      };
+
+      canvas.copy(&self.texture, None, None)?;
+      canvas.present();
+      Ok(())
+   }
+}
+
+fn create_texture(creator: &TextureCreator<WindowContext>, format: PresentFormat, width: usize, height: usize) -> Result<Texture<'_>, String> {
+   creator
+      .create_texture_streaming(format.sdl_format(), width as u32, height as u32)
+      .map_err(|e| e.to_string())
+}
@@ -0,0 +1,450 @@
+use std::ops::Range;
+use glm::{Mat4, Vec2, Vec3, Vec4};
+use crate::camera::{Camera, viewport_matrix};
+use crate::clipping::{clip_line, clip_triangle, triangulate, ClipVertex};
+use crate::color::{srgb_to_linear, Color};
+use crate::framebuffer::Framebuffer;
+use crate::hdr::ToneMapper;
+use crate::light::{Lighting, Surface};
+use crate::line::{draw_line, LineCap, LineStyle};
+use crate::msaa::Msaa;
+use crate::obj_loader::Model;
+use crate::shading::ShadingMode;
+use crate::shader::{FaceData, FragmentShader, StandardFragmentShader, StandardVertexShader, Uniforms, VertexInput, VertexShader};
+use crate::texture::Texture;
+use crate::transform::Transform;
+use crate::tiles::{default_threads, draw_tiled};
+use crate::triangle::{draw_triangle, Fragment, Pixel, Vertex};
+use crate::wireframe::{depth_slope, edge_offset, unique_edges, RenderMode};
+
+// Estado de un objeto (`o`) del modelo o de uno de sus grupos (`g`): se puede
+// ocultar, pintar de otro color o mover sin tocar el resto. `color` es el
+// color elegido por el usuario, que tiene prioridad sobre el del material, y
+// `emission` es luz propia que se suma a la del material (cero si no brilla).
+// `transform` se aplica en el espacio del modelo, antes que la del modelo.
+#[derive(Debug, Clone)]
+pub struct Part {
+   pub visible: bool,
+   pub color: Option<Color>,
+   pub emission: Vec3,
+   pub transform: Transform,
+   // Estado de cada grupo, en el mismo orden que `Object::groups`. Se combina
+   // con el del objeto y los grupos sin entrada quedan como el objeto.
+   pub groups: Vec<Part>,
+}
+
+static DEFAULT_PART: Part = Part {
+   visible: true,
+   color: None,
+   emission: Vec3 { x: 0.0, y: 0.0, z: 0.0 },
+   transform: Transform::IDENTITY,
+   groups: Vec::new(),
+};
+
+impl Default for Part {
+   fn default() -> Self {
+      DEFAULT_PART.clone()
+   }
+}
+
+// Color de las caras que no tienen material ni un color elegido
+const DEFAULT_COLOR: Color = Color { r: 200, g: 210, b: 220 };
+
+// Grupo visible del modelo, con el estado de su objeto ya combinado con el
+// suyo. `uniforms` lleva la matriz de modelo de la parte.
+struct PartState<'a> {
+   faces: Range<usize>,
+   color: Option<Color>,
+   emission: Vec3,
+   uniforms: Uniforms<'a>,
+}
+
+// Cara visible, lista para rasterizar
+struct VisibleFace<'a> {
+   depth: f32,
+   vertices: [Vertex; 3],
+   data: FaceData<'a>,
+}
+
+// Cuántos triángulos se dibujaron y cuántos se descartaron en un cuadro. Las
+// caras que miran hacia atrás o quedan fuera de la vista cuentan como
+// descartadas; una cara recortada puede convertirse en varios triángulos.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RenderStats {
+   pub triangles_drawn: usize,
+   pub triangles_culled: usize,
+}
+
+// Renderizador por software: dibuja un modelo en un Framebuffer con la cámara,
+// las luces y el modo de sombreado configurados
+pub struct Renderer {
+   pub camera: Camera,
+   pub lighting: Lighting,
+   pub shading_mode: ShadingMode,
+   // Textura para las caras cuyo material no trae una propia (map_Kd)
+   pub texture: Option<Texture>,
+   pub texturing: bool,
+   // Estado de cada objeto del modelo, en el mismo orden que `Model::objects`.
+   // Los objetos sin entrada se dibujan visibles y con sus colores normales.
+   pub parts: Vec<Part>,
+   // Caras, aristas o ambas, y cómo se ven las aristas
+   pub render_mode: RenderMode,
+   pub line_style: LineStyle,
+   // Muestras por pixel para suavizar los bordes de las caras
+   pub msaa: Msaa,
+   // Calcular la luz en punto flotante y llevarla a la pantalla con
+   // `tone_mapper`; si no, cada pixel se recorta a 8 bits al sombrearlo
+   pub hdr: bool,
+   pub tone_mapper: ToneMapper,
+   // Hilos para rasterizar por tiles; con 1 se dibuja sobre el framebuffer
+   // completo en el hilo actual. El resultado es el mismo en los dos casos.
+   pub threads: usize,
+   // Programas que transforman los vértices y colorean los pixeles
+   pub vertex_shader: Box<dyn VertexShader>,
+   pub fragment_shader: Box<dyn FragmentShader>,
+}
+
+impl Renderer {
+   pub fn new(camera: Camera, lighting: Lighting) -> Self {
+      Renderer {
+         camera,
+         lighting,
+         shading_mode: ShadingMode::Phong,
+         texture: None,
+         texturing: true,
+         parts: Vec::new(),
+         render_mode: RenderMode::SolidWireframe,
+         line_style: LineStyle { color: Color::new(0, 0, 0), width: 1.0, cap: LineCap::Round, antialiased: true },
+         msaa: Msaa::Off,
+         hdr: false,
+         tone_mapper: ToneMapper::default(),
+         threads: default_threads(),
+         vertex_shader: Box::new(StandardVertexShader),
+         fragment_shader: Box::new(StandardFragmentShader),
+      }
+   }
+
+   // Grupos visibles del modelo, cada uno con su estado y sus matrices
+   fn visible_parts<'a>(&self, model: &Model, uniforms: &Uniforms<'a>) -> Vec<PartState<'a>> {
+      let mut parts = Vec::new();
+      for (object, part) in model.objects.iter().zip(self.parts.iter().chain(std::iter::repeat(&DEFAULT_PART))) {
+         let object_model = uniforms.model * part.transform.model_matrix();
+         let object_normals = uniforms.normal_matrix * part.transform.normal_matrix();
+         let groups = part.groups.iter().chain(std::iter::repeat(&DEFAULT_PART));
+         for (group, group_part) in object.groups.iter().zip(groups) {
+            if !part.visible || !group_part.visible {
+               continue;
+            }
+            parts.push(PartState {
+               faces: group.faces.clone(),
+               color: group_part.color.or(part.color),
+               emission: part.emission + group_part.emission,
+               uniforms: Uniforms {
+                  model: object_model * group_part.transform.model_matrix(),
+                  normal_matrix: object_normals * group_part.transform.normal_matrix(),
+                  ..*uniforms
+               },
+            });
+         }
+      }
+      parts
+   }
+
+   // Dibujar el modelo con la transformación dada. Limpia el z-buffer pero no
+   // el color, para poder dibujar encima de un fondo.
+   pub fn render(&self, model: &Model, transform: &Transform, framebuffer: &mut Framebuffer) -> RenderStats {
+      // Matrices de la cámara, calculadas una vez por cuadro
+      let eye = self.camera.eye;
+      let uniforms = Uniforms {
+         model: transform.model_matrix(),
+         normal_matrix: transform.normal_matrix(),
+         view_projection: self.camera.view_projection_matrix(),
+         eye,
+         lighting: &self.lighting,
+         shading_mode: self.shading_mode,
+      };
+      let viewport = viewport_matrix(framebuffer.width as f32, framebuffer.height as f32);
+
+      // Limpiar el z-buffer con valores grandes
+      for z in &mut framebuffer.z_buffer {
+         *z = f32::MAX; // Usar MAX en lugar de INFINITY para evitar problemas numéricos
+      }
+
+      // Con MSAA las caras se dibujan en las muestras, que empiezan con el fondo
+      framebuffer.set_msaa(self.msaa);
+      framebuffer.set_hdr(self.hdr);
+      if self.hdr {
+         framebuffer.begin_hdr(&self.tone_mapper);
+      } else {
+         framebuffer.begin_samples();
+      }
+
+      // Crear una lista de caras para ordenamiento
+      let mut faces_to_render = Vec::new();
+      let mut culled = 0;
+
+      // Con HDR la luz se calcula en lineal, así que los colores (que se
+      // eligen como se ven en pantalla) se decodifican
+      let albedo = |color: Vec3| if self.hdr {
+         Vec3::new(srgb_to_linear(color.x), srgb_to_linear(color.y), srgb_to_linear(color.z))
+      } else {
+         color
+      };
+
+      // Procesar las caras de las partes visibles (en modo alambre ninguna)
+      let parts = self.visible_parts(model, &uniforms);
+      let faces = parts.iter()
+         .filter(|_| self.render_mode.draws_faces())
+         .flat_map(|part| model.faces[part.faces.clone()].iter().map(move |face| (face, part)));
+      for (face, part) in faces {
+         // Material de la cara: el color elegido para la parte tiene prioridad;
+         // después van los colores del .mtl y si no hay ninguno un color base
+         let material = face.material.map(|m| &model.materials[m]);
+         let default_surface = |color: Color| Surface {
+            albedo: albedo(color.to_vec3()),
+            ambient: Vec3::new(1.0, 1.0, 1.0),
+            specular: Vec3::new(0.35, 0.35, 0.35),
+            shininess: 32.0,
+            emission: part.emission,
+         };
+         let surface = match (part.color, material) {
+            (Some(color), _) => default_surface(color),
+            (None, Some(material)) => Surface {
+               albedo: albedo(material.diffuse),
+               ambient: material.ambient,
+               specular: material.specular,
+               shininess: material.shininess,
+               emission: material.emission + part.emission,
+            },
+            (None, None) => default_surface(DEFAULT_COLOR),
+         };
+
+         // Coordenadas de textura de la cara; sin ellas la cara usa el color base.
+         // La textura del material tiene prioridad sobre la textura global.
+         let uvs = face.texcoords.map(|t| [model.texcoords[t[0]], model.texcoords[t[1]], model.texcoords[t[2]]]);
+         let texture = material
+            .and_then(|m| m.diffuse_map.as_ref())
+            .or(self.texture.as_ref())
+            .filter(|_| self.texturing && uvs.is_some());
+         let textured = texture.is_some();
+         let uvs = uvs.unwrap_or([Vec2::new(0.0, 0.0); 3]);
+
+         // En Gouraud con textura la iluminación de los vértices se calcula sobre
+         // blanco y luego se multiplica por el color de la textura en cada pixel
+         let vertex_surface = Surface {
+            albedo: if textured { Vec3::new(1.0, 1.0, 1.0) } else { surface.albedo },
+            ..surface
+         };
+
+         // Llevar los vértices a espacio de recorte con el vertex shader
+         let clip_vertices: [ClipVertex; 3] = std::array::from_fn(|i| {
+            let input = VertexInput {
+               position: model.vertices[face.vertices[i]],
+               normal: model.normals[face.normals[i]],
+               uv: uvs[i],
+               surface: vertex_surface,
+            };
+            self.vertex_shader.shade(&input, &part.uniforms)
+         });
+         let [rv0, rv1, rv2] = clip_vertices.map(|cv| cv.varyings.world_position);
+
+         // Calcular la normal de la cara para determinar visibilidad
+         let edge1 = Vec3::new(rv1.x - rv0.x, rv1.y - rv0.y, rv1.z - rv0.z);
+         let edge2 = Vec3::new(rv2.x - rv0.x, rv2.y - rv0.y, rv2.z - rv0.z);
+         let normal = Vec3::new(
+            edge1.y * edge2.z - edge1.z * edge2.y,
+            edge1.z * edge2.x - edge1.x * edge2.z,
+            edge1.x * edge2.y - edge1.y * edge2.x
+         );
+
+         // Solo procesar caras que miran hacia la cámara (backface culling)
+         if glm::dot(normal, eye - rv0) <= 0.0 {
+            culled += 1;
+            continue;
+         }
+
+         // Direcciones en el mundo en que crecen U y V sobre la cara
+         let (tangent, bitangent) = tangent_frame(&[rv0, rv1, rv2], &uvs);
+
+         let data = FaceData { surface, normal, texture, material, tangent, bitangent, linear: self.hdr };
+
+         // Recortar contra el volumen de visión
+         let [cv0, cv1, cv2] = clip_vertices;
+         let polygon = clip_triangle(cv0, cv1, cv2);
+         if polygon.len() < 3 {
+            culled += 1;
+         }
+
+         // El polígono recortado puede tener más de tres vértices
+         for [cv0, cv1, cv2] in triangulate(&polygon) {
+            // Transformar a coordenadas de pantalla
+            let to_vertex = |cv: &ClipVertex| Vertex {
+               position: to_screen(&viewport, &cv.position),
+               inv_w: 1.0 / cv.position.w,
+               varyings: cv.varyings,
+            };
+            let tv0 = to_vertex(&cv0);
+            let tv1 = to_vertex(&cv1);
+            let tv2 = to_vertex(&cv2);
+
+            // Calcular la profundidad promedio para ordenamiento
+            let avg_z = (tv0.position.z + tv1.position.z + tv2.position.z) / 3.0;
+
+            // Almacenar solo caras visibles para renderizado
+            faces_to_render.push(VisibleFace {
+               depth: avg_z,
+               vertices: [tv0, tv1, tv2],
+               data,
+            });
+         }
+      }
+
+      // Ordenar las caras de atrás hacia adelante (mayor Z a menor Z)
+      faces_to_render.sort_by(|a, b| {
+         b.depth.partial_cmp(&a.depth).unwrap_or(std::cmp::Ordering::Equal)
+      });
+
+      // Color de un pixel de la cara con el fragment shader
+      let shade = |face: &VisibleFace, fragment: &Fragment| self.fragment_shader.shade(fragment, &face.data, &uniforms);
+
+      // En modo de líneas ocultas las caras solo dejan su profundidad, para
+      // tapar las aristas de atrás; el color se restaura después
+      let background = (self.render_mode == RenderMode::HiddenLine).then(|| framebuffer.buffer.clone());
+
+      // Dibujar las caras y juntar las muestras en los pixeles antes de dibujar
+      // las aristas. Con HDR la luz se guarda sin recortar y se comprime al final.
+      if self.hdr {
+         self.draw_faces(framebuffer, &faces_to_render, shade);
+         framebuffer.resolve_hdr(&self.tone_mapper);
+      } else {
+         self.draw_faces(framebuffer, &faces_to_render, |face, fragment| Color::from_vec3(shade(face, fragment)));
+         framebuffer.resolve();
+      }
+
+      if let Some(background) = background {
+         framebuffer.buffer = background;
+      }
+
+      // Dibujar las aristas después de todas las caras
+      if self.render_mode.draws_edges() {
+         self.draw_wireframe(model, &parts, &uniforms, framebuffer);
+      }
+
+      RenderStats { triangles_drawn: faces_to_render.len(), triangles_culled: culled }
+   }
+
+   // Dibujar las caras en orden, en este hilo o repartidas en tiles
+   fn draw_faces<P, S>(&self, framebuffer: &mut Framebuffer, faces: &[VisibleFace], shade: S)
+   where
+      P: Pixel,
+      S: Fn(&VisibleFace, &Fragment) -> P + Sync,
+   {
+      let opacity = |face: &VisibleFace| face.data.material.map_or(1.0, |m| m.opacity);
+      if self.threads <= 1 {
+         for face in faces {
+            let [tv0, tv1, tv2] = &face.vertices;
+            draw_triangle(framebuffer, tv0, tv1, tv2, opacity(face), |fragment| shade(face, fragment));
+         }
+      } else {
+         draw_tiled(
+            framebuffer,
+            faces,
+            self.threads,
+            |face| face.vertices.map(|v| v.position),
+            |tile, face| {
+               let [tv0, tv1, tv2] = &face.vertices;
+               tile.draw_triangle(tv0, tv1, tv2, opacity(face), |fragment| shade(face, fragment));
+            },
+         );
+      }
+   }
+
+   // Dibujar cada arista de las partes visibles una sola vez. Salvo en modo
+   // alambre se omiten las aristas que solo tocan caras traseras y el resto se
+   // prueba contra el z-buffer que dejaron las caras.
+   fn draw_wireframe(&self, model: &Model, parts: &[PartState], uniforms: &Uniforms, framebuffer: &mut Framebuffer) {
+      let viewport = viewport_matrix(framebuffer.width as f32, framebuffer.height as f32);
+      let depth_test = self.render_mode.draws_faces();
+
+      // Llevar los vértices al mundo una sola vez, aunque los compartan
+      // varias caras, con la matriz de la parte que los usa (las partes no
+      // suelen compartir vértices; si lo hacen vale la última)
+      let mut matrices = vec![&uniforms.model; model.vertices.len()];
+      for part in parts {
+         for face in &model.faces[part.faces.clone()] {
+            for &i in &face.vertices {
+               matrices[i] = &part.uniforms.model;
+            }
+         }
+      }
+      let world: Vec<Vec3> = model.vertices.iter().zip(&matrices).map(|(v, m)| to_world(m, v)).collect();
+
+      let clip: Vec<Vec4> = world.iter().map(|p| to_clip(&uniforms.view_projection, p)).collect();
+
+      let faces = parts.iter().flat_map(|part| &model.faces[part.faces.clone()]).map(|face| {
+         let [p0, p1, p2] = face.vertices.map(|i| world[i]);
+         let normal = glm::cross(p1 - p0, p2 - p0);
+         let front = glm::dot(normal, uniforms.eye - p0) > 0.0;
+         // Pendiente de profundidad en pantalla, si la cara no cruza el plano
+         // de la cámara
+         let corners = face.vertices.map(|i| clip[i]);
+         let slope = if depth_test && corners.iter().all(|c| c.w > 0.0) {
+            let [s0, s1, s2] = corners.map(|c| to_screen(&viewport, &c));
+            depth_slope(s0, s1, s2)
+         } else {
+            0.0
+         };
+         (face.vertices, front, slope)
+      });
+
+      for edge in unique_edges(faces) {
+         if depth_test && !edge.front {
+            continue;
+         }
+
+         // Recortar la arista contra el volumen de visión
+         if let Some((a, b)) = clip_line(clip[edge.a], clip[edge.b]) {
+            let offset = depth_test.then(|| edge_offset(edge.slope, &self.line_style));
+            draw_line(framebuffer, to_screen(&viewport, &a), to_screen(&viewport, &b), &self.line_style, offset);
+         }
+      }
+   }
+}
+
+// Tangente y bitangente de un triángulo: hacia dónde crecen U y V en el mundo.
+// Si las UV son degeneradas se devuelven vectores nulos (sin relieve).
+fn tangent_frame(positions: &[Vec3; 3], uvs: &[Vec2; 3]) -> (Vec3, Vec3) {
+   let e1 = positions[1] - positions[0];
+   let e2 = positions[2] - positions[0];
+   let duv1 = uvs[1] - uvs[0];
+   let duv2 = uvs[2] - uvs[0];
+
+   let det = duv1.x * duv2.y - duv2.x * duv1.y;
+   if det.abs() < 1e-8 {
+      let zero = Vec3::new(0.0, 0.0, 0.0);
+      return (zero, zero);
+   }
+
+   let r = 1.0 / det;
+   ((e1 * duv2.y - e2 * duv1.y) * r, (e2 * duv1.x - e1 * duv2.x) * r)
+}
+
+// Llevar un punto del modelo al mundo con la matriz de modelo
+fn to_world(model: &Mat4, p: &Vec3) -> Vec3 {
+   let world = *model * Vec4::new(p.x, p.y, p.z, 1.0);
+   Vec3::new(world.x, world.y, world.z)
+}
+
+// Llevar un punto del mundo a espacio de recorte (coordenadas homogéneas)
+fn to_clip(view_projection: &Mat4, p: &Vec3) -> Vec4 {
+   *view_projection * Vec4::new(p.x, p.y, p.z, 1.0)
+}
+
+// División de perspectiva y viewport: x, y en pixeles y z como profundidad en [0, 1].
+// Solo debe usarse con puntos ya recortados (w > 0).
+fn to_screen(viewport: &Mat4, clip: &Vec4) -> Vec3 {
+   let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+   let screen = *viewport * ndc;
+   Vec3::new(screen.x, screen.y, screen.z)
+}
+
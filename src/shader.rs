@@ -0,0 +1,204 @@
+use glm::{Mat3, Mat4, Vec2, Vec3, Vec4};
+use crate::clipping::ClipVertex;
+use crate::light::{Lighting, Surface};
+use crate::material::Material;
+use crate::shading::ShadingMode;
+use crate::texture::Texture;
+use crate::triangle::{Fragment, Varyings};
+
+// Valores iguales para todos los vértices y pixeles de un cuadro
+pub struct Uniforms<'a> {
+   // Matriz de modelo y la que corresponde a las normales (ver Transform)
+   pub model: Mat4,
+   pub normal_matrix: Mat3,
+   pub view_projection: Mat4,
+   pub eye: Vec3,
+   pub lighting: &'a Lighting,
+   pub shading_mode: ShadingMode,
+}
+
+// Un vértice del modelo tal como lo recibe el vertex shader. `surface` es el
+// material de su cara (con albedo blanco si la cara lleva textura, para que la
+// textura se aplique por pixel).
+#[derive(Debug, Clone, Copy)]
+pub struct VertexInput {
+   pub position: Vec3,
+   pub normal: Vec3,
+   pub uv: Vec2,
+   pub surface: Surface,
+}
+
+// Datos de la cara que se está dibujando, para el fragment shader
+#[derive(Clone, Copy)]
+pub struct FaceData<'a> {
+   pub surface: Surface,
+   // Normal de la cara en el mundo (sin normalizar)
+   pub normal: Vec3,
+   pub texture: Option<&'a Texture>,
+   pub material: Option<&'a Material>,
+   // Tangente y bitangente en el mundo (dirección de U y V), para el relieve
+   pub tangent: Vec3,
+   pub bitangent: Vec3,
+   // Con HDR la textura se lee como luz lineal
+   pub linear: bool,
+}
+
+impl FaceData<'_> {
+   // Color de la textura en este pixel, si la cara tiene
+   pub fn texel(&self, varyings: &Varyings) -> Option<Vec3> {
+      self.texture.map(|t| if self.linear { t.sample_linear(varyings.uv) } else { t.sample(varyings.uv) })
+   }
+
+   // Normal perturbada por el relieve del material (map_Bump), si lo tiene
+   pub fn bumped(&self, normal: Vec3, varyings: &Varyings) -> Vec3 {
+      match self.material {
+         Some(material) => material.bump_normal(normal, self.tangent, self.bitangent, varyings.uv),
+         None => normal,
+      }
+   }
+}
+
+// Lleva cada vértice del modelo a espacio de recorte y calcula los atributos
+// que el rasterizador interpola. `world_position` debe quedar en el mundo: con
+// ella se descartan las caras traseras y se calcula la normal de la cara.
+pub trait VertexShader: Send + Sync {
+   fn shade(&self, input: &VertexInput, uniforms: &Uniforms) -> ClipVertex;
+}
+
+// Calcula el color de cada pixel cubierto, con componentes en [0, 1] (lo que
+// se salga se recorta al escribirlo). Con HDR es luz lineal y puede pasar de 1.
+pub trait FragmentShader: Send + Sync {
+   fn shade(&self, fragment: &Fragment, face: &FaceData, uniforms: &Uniforms) -> Vec3;
+}
+
+// Transformación del modelo e iluminación por vértice en modo Gouraud
+pub struct StandardVertexShader;
+
+impl VertexShader for StandardVertexShader {
+   fn shade(&self, input: &VertexInput, uniforms: &Uniforms) -> ClipVertex {
+      let world = uniforms.model * Vec4::new(input.position.x, input.position.y, input.position.z, 1.0);
+      let p = Vec3::new(world.x, world.y, world.z);
+      let n = uniforms.normal_matrix * input.normal;
+      ClipVertex {
+         position: uniforms.view_projection * world,
+         varyings: Varyings {
+            world_position: p,
+            normal: n,
+            color: match uniforms.shading_mode {
+               ShadingMode::Gouraud => uniforms.lighting.shade(&input.surface, p, n, uniforms.eye),
+               _ => Vec3::new(0.0, 0.0, 0.0),
+            },
+            uv: input.uv,
+            custom: Vec4::new(0.0, 0.0, 0.0, 0.0),
+         },
+      }
+   }
+}
+
+// Texturas, relieve e iluminación según el modo de sombreado
+pub struct StandardFragmentShader;
+
+impl FragmentShader for StandardFragmentShader {
+   fn shade(&self, fragment: &Fragment, face: &FaceData, uniforms: &Uniforms) -> Vec3 {
+      let varyings = &fragment.varyings;
+      // Color de la textura en este pixel (las UV ya vienen con corrección de
+      // perspectiva)
+      let texel = face.texel(varyings);
+      let surface = Surface { albedo: texel.unwrap_or(face.surface.albedo), ..face.surface };
+      let lighting = uniforms.lighting;
+      let eye = uniforms.eye;
+
+      match uniforms.shading_mode {
+         // Normal de la cara, iluminada por pixel
+         ShadingMode::Flat => lighting.shade(&surface, varyings.world_position, face.bumped(face.normal, varyings), eye),
+         // Color ya iluminado en los vértices e interpolado
+         ShadingMode::Gouraud => match texel {
+            Some(texel) => varyings.color * texel,
+            None => varyings.color,
+         },
+         // Normal interpolada, iluminada por pixel
+         ShadingMode::Phong => lighting.shade(&surface, varyings.world_position, face.bumped(varyings.normal, varyings), eye),
+      }
+   }
+}
+
+// Sombreado de caricatura: la luz difusa se reduce a unas pocas bandas y los
+// bordes que miran de lado se oscurecen como un contorno
+pub struct ToonShader {
+   pub bands: u32,
+}
+
+impl FragmentShader for ToonShader {
+   fn shade(&self, fragment: &Fragment, face: &FaceData, uniforms: &Uniforms) -> Vec3 {
+      let varyings = &fragment.varyings;
+      let albedo = face.texel(varyings).unwrap_or(face.surface.albedo);
+      let n = glm::normalize(varyings.normal);
+      let v = glm::normalize(uniforms.eye - varyings.world_position);
+
+      // Silueta: la normal casi perpendicular a la vista
+      if glm::dot(n, v) < 0.2 {
+         return Vec3::new(0.0, 0.0, 0.0);
+      }
+
+      let light = uniforms.lighting.shade(
+         &Surface { albedo: Vec3::new(1.0, 1.0, 1.0), specular: Vec3::new(0.0, 0.0, 0.0), ..face.surface },
+         varyings.world_position,
+         n,
+         uniforms.eye,
+      );
+      let intensity = (light.x + light.y + light.z) / 3.0;
+      let bands = self.bands.max(1) as f32;
+      let level = ((intensity * bands).floor() + 1.0).min(bands) / bands;
+      albedo * level
+   }
+}
+
+// Visualización de normales: cada componente de la normal en el mundo se
+// pasa de [-1, 1] a [0, 1]
+pub struct NormalShader;
+
+impl FragmentShader for NormalShader {
+   fn shade(&self, fragment: &Fragment, _face: &FaceData, _uniforms: &Uniforms) -> Vec3 {
+      let n = glm::normalize(fragment.varyings.normal);
+      (n + Vec3::new(1.0, 1.0, 1.0)) * 0.5
+   }
+}
+
+// Holograma: color plano con brillo en los bordes (efecto Fresnel) y líneas
+// horizontales de barrido en pantalla
+pub struct HologramShader {
+   pub color: Vec3,
+}
+
+impl FragmentShader for HologramShader {
+   fn shade(&self, fragment: &Fragment, _face: &FaceData, uniforms: &Uniforms) -> Vec3 {
+      let varyings = &fragment.varyings;
+      let n = glm::normalize(varyings.normal);
+      let v = glm::normalize(uniforms.eye - varyings.world_position);
+      let rim = (1.0 - glm::dot(n, v).abs()).powf(2.0);
+      let scanline = if fragment.y % 4 < 2 { 1.0 } else { 0.55 };
+      self.color * ((0.25 + rim) * scanline)
+   }
+}
+
+// Destello de daño: mezcla el resultado de otro shader con `color` según
+// `intensity` (0 = sin destello, 1 = todo del color del destello)
+pub struct FlashShader<S> {
+   pub inner: S,
+   pub color: Vec3,
+   pub intensity: f32,
+}
+
+impl<S: FragmentShader> FragmentShader for FlashShader<S> {
+   fn shade(&self, fragment: &Fragment, face: &FaceData, uniforms: &Uniforms) -> Vec3 {
+      let base = self.inner.shade(fragment, face, uniforms);
+      let t = self.intensity.clamp(0.0, 1.0);
+      base * (1.0 - t) + self.color * t
+   }
+}
+
+impl FragmentShader for Box<dyn FragmentShader> {
+   fn shade(&self, fragment: &Fragment, face: &FaceData, uniforms: &Uniforms) -> Vec3 {
+      self.as_ref().shade(fragment, face, uniforms)
+   }
+}
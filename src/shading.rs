@@ -0,0 +1,20 @@
+// Modos de sombreado disponibles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadingMode {
+   // Una normal por cara: se ven las facetas
+   Flat,
+   // Iluminación por vértice, el color se interpola en el triángulo
+   Gouraud,
+   // La normal se interpola y la iluminación se calcula por pixel
+   Phong,
+}
+
+impl ShadingMode {
+   pub fn name(self) -> &'static str {
+      match self {
+         ShadingMode::Flat => "Flat",
+         ShadingMode::Gouraud => "Gouraud",
+         ShadingMode::Phong => "Phong",
+      }
+   }
+}
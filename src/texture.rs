@@ -0,0 +1,80 @@
+use std::io;
+use std::path::Path;
+use glm::{Vec2, Vec3};
+use crate::color::Color;
+use crate::image::Image;
+
+// Filtro usado al leer la textura
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+   // El texel más cercano (se ven los pixeles de la textura)
+   Nearest,
+   // Mezcla de los cuatro texels vecinos
+   Bilinear,
+}
+
+impl Filter {
+   pub fn name(self) -> &'static str {
+      match self {
+         Filter::Nearest => "Nearest",
+         Filter::Bilinear => "Bilinear",
+      }
+   }
+}
+
+pub struct Texture {
+   pub image: Image,
+   pub filter: Filter,
+}
+
+impl Texture {
+   pub fn new(image: Image) -> Self {
+      Texture { image, filter: Filter::Bilinear }
+   }
+
+   pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+      Ok(Texture::new(Image::load(path)?))
+   }
+
+   // Texel en coordenadas enteras, repitiendo la textura fuera de sus bordes
+   fn texel(&self, x: i64, y: i64, decode: fn(Color) -> Vec3) -> Vec3 {
+      let x = x.rem_euclid(self.image.width as i64) as usize;
+      let y = y.rem_euclid(self.image.height as i64) as usize;
+      decode(self.image.get(x, y))
+   }
+
+   // Leer el color de la textura en las coordenadas (u, v). Como en OBJ,
+   // v = 0 corresponde a la fila inferior de la imagen.
+   pub fn sample(&self, uv: Vec2) -> Vec3 {
+      self.filtered(uv, Color::to_vec3)
+   }
+
+   // Igual que `sample`, pero como luz lineal (para HDR). Los texels se pasan
+   // a lineal antes de filtrarlos.
+   pub fn sample_linear(&self, uv: Vec2) -> Vec3 {
+      self.filtered(uv, Color::to_linear)
+   }
+
+   fn filtered(&self, uv: Vec2, decode: fn(Color) -> Vec3) -> Vec3 {
+      let x = uv.x * self.image.width as f32;
+      let y = (1.0 - uv.y) * self.image.height as f32;
+
+      match self.filter {
+         Filter::Nearest => self.texel(x.floor() as i64, y.floor() as i64, decode),
+         Filter::Bilinear => {
+            // Los centros de los texels están en medio de cada pixel
+            let x = x - 0.5;
+            let y = y - 0.5;
+            let x0 = x.floor();
+            let y0 = y.floor();
+            let tx = x - x0;
+            let ty = y - y0;
+            let (x0, y0) = (x0 as i64, y0 as i64);
+
+            let top = self.texel(x0, y0, decode) * (1.0 - tx) + self.texel(x0 + 1, y0, decode) * tx;
+            let bottom = self.texel(x0, y0 + 1, decode) * (1.0 - tx) + self.texel(x0 + 1, y0 + 1, decode) * tx;
+            top * (1.0 - ty) + bottom * ty
+         },
+      }
+   }
+}
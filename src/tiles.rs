@@ -0,0 +1,146 @@
+use std::sync::Mutex;
+use glm::Vec3;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::triangle::{pixel_bounds, rasterize, Fragment, Pixel, Target, Vertex};
+
+// Lado de cada tile en pixeles
+pub const TILE_SIZE: usize = 64;
+
+// Trozo rectangular del framebuffer con su propio color y z-buffer, para que
+// cada hilo dibuje sin tocar la memoria de los demás. Con MSAA guarda las
+// muestras de sus pixeles, y con HDR su luz en lugar del color.
+pub struct Tile<C: Pixel = Color> {
+   pub x: usize,
+   pub y: usize,
+   pub width: usize,
+   pub height: usize,
+   pub buffer: Vec<C>,
+   pub z_buffer: Vec<f32>,
+   pattern: &'static [(i32, i32)],
+   // Triángulos que tocan este tile, en el orden en que hay que dibujarlos
+   bin: Vec<usize>,
+}
+
+impl<C: Pixel> Tile<C> {
+   // Copiar la zona del framebuffer que cubre el tile
+   fn load(framebuffer: &Framebuffer, x: usize, y: usize, width: usize, height: usize) -> Self {
+      let samples = framebuffer.msaa.samples();
+      let (colors, depths) = C::samples(framebuffer);
+      let mut buffer = Vec::with_capacity(width * height * samples);
+      let mut z_buffer = Vec::with_capacity(width * height * samples);
+      for row in y..y + height {
+         let start = (row * framebuffer.width + x) * samples;
+         buffer.extend_from_slice(&colors[start..start + width * samples]);
+         z_buffer.extend_from_slice(&depths[start..start + width * samples]);
+      }
+      Tile { x, y, width, height, buffer, z_buffer, pattern: framebuffer.msaa.pattern(), bin: Vec::new() }
+   }
+
+   // Devolver el contenido del tile a su lugar en el framebuffer
+   fn store(&self, framebuffer: &mut Framebuffer) {
+      let samples = self.pattern.len();
+      let row_length = self.width * samples;
+      let framebuffer_width = framebuffer.width;
+      let (colors, depths) = C::samples_mut(framebuffer);
+      for row in 0..self.height {
+         let start = ((self.y + row) * framebuffer_width + self.x) * samples;
+         let tile_start = row * row_length;
+         colors[start..start + row_length].copy_from_slice(&self.buffer[tile_start..tile_start + row_length]);
+         depths[start..start + row_length].copy_from_slice(&self.z_buffer[tile_start..tile_start + row_length]);
+      }
+   }
+
+   // Dibujar un triángulo recortado al tile; mismos parámetros que `draw_triangle`
+   pub fn draw_triangle<F>(&mut self, v0: &Vertex, v1: &Vertex, v2: &Vertex, opacity: f32, shade: F)
+   where
+      F: FnMut(&Fragment) -> C,
+   {
+      let target = Target {
+         x0: self.x as i32,
+         y0: self.y as i32,
+         x1: (self.x + self.width) as i32,
+         y1: (self.y + self.height) as i32,
+         buffer: &mut self.buffer,
+         z_buffer: &mut self.z_buffer,
+         pattern: self.pattern,
+      };
+      rasterize(target, v0, v1, v2, opacity, shade);
+   }
+}
+
+// Número de hilos a usar por defecto: uno por núcleo
+pub fn default_threads() -> usize {
+   std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+// Dibujar una lista de triángulos repartiendo la pantalla en tiles que se
+// rasterizan en paralelo con `threads` hilos. Cada triángulo se asigna a los
+// tiles que toca su rectángulo envolvente (`positions` da sus vértices en
+// pantalla) y cada tile los dibuja con `draw` en el mismo orden de la lista,
+// así que el resultado es idéntico a dibujarlos uno tras otro sobre el
+// framebuffer completo.
+pub fn draw_tiled<T, C, P, F>(framebuffer: &mut Framebuffer, triangles: &[T], threads: usize, positions: P, draw: F)
+where
+   T: Sync,
+   C: Pixel,
+   P: Fn(&T) -> [Vec3; 3],
+   F: Fn(&mut Tile<C>, &T) + Sync,
+{
+   let columns = framebuffer.width.div_ceil(TILE_SIZE);
+   let rows = framebuffer.height.div_ceil(TILE_SIZE);
+
+   let mut tiles = Vec::with_capacity(columns * rows);
+   for row in 0..rows {
+      for column in 0..columns {
+         let x = column * TILE_SIZE;
+         let y = row * TILE_SIZE;
+         let width = TILE_SIZE.min(framebuffer.width - x);
+         let height = TILE_SIZE.min(framebuffer.height - y);
+         tiles.push(Tile::load(framebuffer, x, y, width, height));
+      }
+   }
+
+   // Repartir los triángulos en los tiles con el mismo rectángulo que usa el
+   // rasterizador (posiciones redondeadas a punto fijo)
+   for (i, triangle) in triangles.iter().enumerate() {
+      let (min_x, min_y, max_x, max_y) = pixel_bounds(positions(triangle));
+      let min_x = min_x.max(0);
+      let min_y = min_y.max(0);
+      let max_x = max_x.min(framebuffer.width as i64 - 1);
+      let max_y = max_y.min(framebuffer.height as i64 - 1);
+      if min_x > max_x || min_y > max_y {
+         continue;
+      }
+
+      for row in min_y as usize / TILE_SIZE..=max_y as usize / TILE_SIZE {
+         for column in min_x as usize / TILE_SIZE..=max_x as usize / TILE_SIZE {
+            tiles[row * columns + column].bin.push(i);
+         }
+      }
+   }
+
+   // Cada hilo toma el siguiente tile pendiente hasta que no quede ninguno;
+   // así los tiles con muchos triángulos no frenan a un solo hilo
+   {
+      let queue = Mutex::new(tiles.iter_mut().filter(|tile| !tile.bin.is_empty()));
+      let worker = || loop {
+         let Some(tile) = queue.lock().unwrap().next() else {
+            break;
+         };
+         for i in std::mem::take(&mut tile.bin) {
+            draw(tile, &triangles[i]);
+         }
+      };
+      std::thread::scope(|scope| {
+         for _ in 1..threads.max(1) {
+            scope.spawn(worker);
+         }
+         worker();
+      });
+   }
+
+   for tile in &tiles {
+      tile.store(framebuffer);
+   }
+}
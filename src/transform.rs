@@ -0,0 +1,159 @@
+use std::ops::Mul;
+use glm::{Mat3, Mat4, Vec3, Vec4};
+use crate::obj_loader::Model;
+
+// Cuaternión unitario que representa una rotación. Componer rotaciones con
+// cuaterniones no sufre bloqueo de cardán como los ángulos de Euler.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quat {
+   pub w: f32,
+   pub x: f32,
+   pub y: f32,
+   pub z: f32,
+}
+
+impl Quat {
+   pub const IDENTITY: Quat = Quat { w: 1.0, x: 0.0, y: 0.0, z: 0.0 };
+
+   // Rotación de `angle` radianes alrededor de `axis` (regla de la mano derecha)
+   pub fn from_axis_angle(axis: Vec3, angle: f32) -> Self {
+      let axis = glm::normalize(axis);
+      let (sin, cos) = (angle / 2.0).sin_cos();
+      Quat { w: cos, x: axis.x * sin, y: axis.y * sin, z: axis.z * sin }
+   }
+
+   // Volver a longitud 1; al componer muchas rotaciones se acumula error
+   pub fn normalize(self) -> Self {
+      let length = (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+      if length == 0.0 {
+         return Quat::IDENTITY;
+      }
+      Quat { w: self.w / length, x: self.x / length, y: self.y / length, z: self.z / length }
+   }
+
+   // Rotar un vector
+   pub fn rotate(self, v: Vec3) -> Vec3 {
+      self.to_mat3() * v
+   }
+
+   // Matriz de rotación equivalente
+   pub fn to_mat3(self) -> Mat3 {
+      let Quat { w, x, y, z } = self;
+      Mat3::new(
+         Vec3::new(1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y + w * z), 2.0 * (x * z - w * y)),
+         Vec3::new(2.0 * (x * y - w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z + w * x)),
+         Vec3::new(2.0 * (x * z + w * y), 2.0 * (y * z - w * x), 1.0 - 2.0 * (x * x + y * y)),
+      )
+   }
+
+   // Ángulos de Euler en radianes (x, y, z) tales que la rotación equivale a
+   // girar primero x en X, luego y en Y y por último z en Z. Solo sirven para
+   // mostrarlos: cerca de y = ±90 grados x y z dejan de estar bien definidos.
+   pub fn to_euler(self) -> Vec3 {
+      let Quat { w, x, y, z } = self.normalize();
+      Vec3::new(
+         (2.0 * (w * x + y * z)).atan2(1.0 - 2.0 * (x * x + y * y)),
+         (2.0 * (w * y - z * x)).clamp(-1.0, 1.0).asin(),
+         (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z)),
+      )
+   }
+}
+
+// `a * b` aplica primero `b` y después `a`
+impl Mul for Quat {
+   type Output = Quat;
+
+   fn mul(self, o: Quat) -> Quat {
+      Quat {
+         w: self.w * o.w - self.x * o.x - self.y * o.y - self.z * o.z,
+         x: self.w * o.x + self.x * o.w + self.y * o.z - self.z * o.y,
+         y: self.w * o.y - self.x * o.z + self.y * o.w + self.z * o.x,
+         z: self.w * o.z + self.x * o.y - self.y * o.x + self.z * o.w,
+      }
+   }
+}
+
+// Posición del modelo en el mundo. Un punto del modelo se lleva al mundo
+// restándole `pivot`, escalándolo por `scale` en cada eje (del modelo),
+// rotándolo con `rotation` y desplazándolo a `translation`.
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+   pub translation: Vec3,
+   pub rotation: Quat,
+   pub scale: Vec3,
+   // Punto del modelo alrededor del cual se rota y escala
+   pub pivot: Vec3,
+}
+
+impl Transform {
+   // No mueve, gira ni escala nada
+   pub const IDENTITY: Transform = Transform {
+      translation: Vec3 { x: 0.0, y: 0.0, z: 0.0 },
+      rotation: Quat::IDENTITY,
+      scale: Vec3 { x: 1.0, y: 1.0, z: 1.0 },
+      pivot: Vec3 { x: 0.0, y: 0.0, z: 0.0 },
+   };
+
+   // Centrar el modelo en el origen y escalarlo para que mida 2 unidades en su
+   // lado más largo, rotado primero `angle_x` en X y luego `angle_y` en Y
+   pub fn fit(model: &Model, angle_x: f32, angle_y: f32) -> Self {
+      let size = model.calculate_size();
+      let scale = 2.0 / size.x.max(size.y).max(size.z);
+      Transform {
+         translation: Vec3::new(0.0, 0.0, 0.0),
+         rotation: Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), angle_y)
+            * Quat::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), angle_x),
+         scale: Vec3::new(scale, scale, scale),
+         pivot: model.calculate_center(),
+      }
+   }
+
+   // Girar el modelo `angle` radianes alrededor de un eje del mundo que pasa
+   // por su pivote (por ejemplo X de la pantalla, sin importar cómo esté girado)
+   pub fn rotate(&mut self, axis: Vec3, angle: f32) {
+      self.rotation = (Quat::from_axis_angle(axis, angle) * self.rotation).normalize();
+   }
+
+   // Matriz de modelo: T(translation) * R * S * T(-pivot)
+   pub fn model_matrix(&self) -> Mat4 {
+      let r = self.rotation.to_mat3();
+      let s = self.scale;
+      let x = r[0] * s.x;
+      let y = r[1] * s.y;
+      let z = r[2] * s.z;
+      let t = self.translation - (x * self.pivot.x + y * self.pivot.y + z * self.pivot.z);
+      Mat4::new(
+         Vec4::new(x.x, x.y, x.z, 0.0),
+         Vec4::new(y.x, y.y, y.z, 0.0),
+         Vec4::new(z.x, z.y, z.z, 0.0),
+         Vec4::new(t.x, t.y, t.z, 1.0),
+      )
+   }
+
+   // Matriz para las normales: la inversa traspuesta de R * S, que es R * S⁻¹.
+   // Con escala no uniforme las normales no se pueden transformar como puntos.
+   pub fn normal_matrix(&self) -> Mat3 {
+      let r = self.rotation.to_mat3();
+      let inverse = |s: f32| if s == 0.0 { 0.0 } else { 1.0 / s };
+      Mat3::new(r[0] * inverse(self.scale.x), r[1] * inverse(self.scale.y), r[2] * inverse(self.scale.z))
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use glm::Vec3;
+   use super::Quat;
+
+   #[test]
+   fn euler_angles_round_trip() {
+      let (x, y, z) = (-0.7_f32, 0.4_f32, 2.5_f32);
+      let rotation = Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), z)
+         * Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), y)
+         * Quat::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), x);
+      let angles = rotation.to_euler();
+      assert!(
+         (angles.x - x).abs() < 1e-5 && (angles.y - y).abs() < 1e-5 && (angles.z - z).abs() < 1e-5,
+         "se esperaba ({}, {}, {}) y se obtuvo ({}, {}, {})", x, y, z, angles.x, angles.y, angles.z,
+      );
+   }
+}
@@ -1,120 +1,332 @@
 use crate::framebuffer::Framebuffer;
 use crate::color::Color;
-use glm::Vec3;
+use crate::msaa::MAX_SAMPLES;
+use glm::{Vec2, Vec3, Vec4};
 
-pub fn draw_triangle(
-   framebuffer: &mut Framebuffer,
-   v0: &Vec3,
-   v1: &Vec3,
-   v2: &Vec3,
-   color: Color,
-) {
-   // Convertir vértices a coordenadas de pantalla (coordenadas enteras)
-   let x0 = v0.x as i32;
-   let y0 = v0.y as i32;
-   let z0 = v0.z;
-   
-   let x1 = v1.x as i32;
-   let y1 = v1.y as i32;
-   let z1 = v1.z;
-   
-   let x2 = v2.x as i32;
-   let y2 = v2.y as i32;
-   let z2 = v2.z;
-   
-   // Ordenar vértices por coordenada Y (de arriba a abajo)
-   let mut vertices = [(x0, y0, z0), (x1, y1, z1), (x2, y2, z2)];
-   if vertices[0].1 > vertices[1].1 {
-      vertices.swap(0, 1);
+// Atributos que se interpolan a lo largo del triángulo
+#[derive(Debug, Clone, Copy)]
+pub struct Varyings {
+   pub world_position: Vec3,
+   pub normal: Vec3,
+   pub color: Vec3,
+   pub uv: Vec2,
+   // Libre para los shaders propios (ver shader.rs); se interpola igual que el
+   // resto
+   pub custom: Vec4,
+}
+
+impl Varyings {
+   pub fn lerp(&self, other: &Varyings, t: f32) -> Varyings {
+      Varyings {
+         world_position: self.world_position + (other.world_position - self.world_position) * t,
+         normal: self.normal + (other.normal - self.normal) * t,
+         color: self.color + (other.color - self.color) * t,
+         uv: self.uv + (other.uv - self.uv) * t,
+         custom: self.custom + (other.custom - self.custom) * t,
+      }
+   }
+
+   // Combinación de los atributos de tres vértices con los pesos dados
+   fn weighted(a: &Varyings, b: &Varyings, c: &Varyings, w0: f32, w1: f32, w2: f32) -> Varyings {
+      Varyings {
+         world_position: a.world_position * w0 + b.world_position * w1 + c.world_position * w2,
+         normal: a.normal * w0 + b.normal * w1 + c.normal * w2,
+         color: a.color * w0 + b.color * w1 + c.color * w2,
+         uv: a.uv * w0 + b.uv * w1 + c.uv * w2,
+         custom: a.custom * w0 + b.custom * w1 + c.custom * w2,
+      }
+   }
+}
+
+// Vértice listo para rasterizar: posición en pantalla (x, y en pixeles y z como
+// profundidad), 1/w para corregir la perspectiva y los atributos a interpolar
+#[derive(Debug, Clone, Copy)]
+pub struct Vertex {
+   pub position: Vec3,
+   pub inv_w: f32,
+   pub varyings: Varyings,
+}
+
+// Pixel cubierto por un triángulo, tal como lo recibe el sombreado
+#[derive(Debug, Clone, Copy)]
+pub struct Fragment {
+   pub x: i32,
+   pub y: i32,
+   pub depth: f32,
+   // Pesos de cada vértice donde se sombrea el pixel, con corrección de
+   // perspectiva; suman 1. Es el centro del pixel, salvo con MSAA en los
+   // bordes, donde se usa el centro de las muestras cubiertas.
+   pub barycentric: Vec3,
+   pub varyings: Varyings,
+}
+
+// Bits de subpixel de las coordenadas en punto fijo: las posiciones se
+// redondean a 1/256 de pixel, así que las aristas compartidas dan exactamente
+// los mismos valores en los dos triángulos
+const SUBPIXEL_BITS: u32 = 8;
+const SUBPIXEL: i64 = 1 << SUBPIXEL_BITS;
+
+fn to_fixed(v: f32) -> i64 {
+   (v * SUBPIXEL as f32).round() as i64
+}
+
+// Pixeles que puede cubrir un triángulo con vértices en punto fijo, sin
+// recortar: (min_x, min_y, max_x, max_y)
+fn fixed_bounds(points: [(i64, i64); 3]) -> (i64, i64, i64, i64) {
+   let [p0, p1, p2] = points;
+   (
+      p0.0.min(p1.0).min(p2.0) >> SUBPIXEL_BITS,
+      p0.1.min(p1.1).min(p2.1) >> SUBPIXEL_BITS,
+      p0.0.max(p1.0).max(p2.0) >> SUBPIXEL_BITS,
+      p0.1.max(p1.1).max(p2.1) >> SUBPIXEL_BITS,
+   )
+}
+
+// El mismo rectángulo a partir de las posiciones en pantalla, redondeadas
+// igual que al rasterizar. Sirve para repartir los triángulos en tiles.
+pub fn pixel_bounds(positions: [Vec3; 3]) -> (i64, i64, i64, i64) {
+   fixed_bounds(positions.map(|p| (to_fixed(p.x), to_fixed(p.y))))
+}
+
+// Función de arista a -> b evaluada en punto fijo: a * x + b * y + c. Es
+// positiva a la derecha de la arista en pantalla (con Y hacia abajo).
+struct EdgeFunction {
+   a: i64,
+   b: i64,
+   c: i64,
+   // Regla arriba-izquierda: los pixeles justo sobre la arista solo son del
+   // triángulo si es una arista superior o izquierda, así que no se dibujan
+   // dos veces ni quedan huecos entre triángulos vecinos
+   bias: i64,
+}
+
+impl EdgeFunction {
+   fn new(from: (i64, i64), to: (i64, i64)) -> Self {
+      let dx = to.0 - from.0;
+      let dy = to.1 - from.1;
+      let top_left = (dy == 0 && dx > 0) || dy < 0;
+      EdgeFunction {
+         a: -dy,
+         b: dx,
+         c: from.0 * to.1 - from.1 * to.0,
+         bias: if top_left { 0 } else { -1 },
+      }
+   }
+
+   fn at(&self, x: i64, y: i64) -> i64 {
+      self.a * x + self.b * y + self.c
+   }
+}
+
+// Zona donde se dibuja: el framebuffer completo o un tile. Cubre los pixeles
+// [x0, x1) x [y0, y1) de la pantalla, guardados fila por fila, cada uno con
+// una muestra por posición de `pattern` (ver Msaa::pattern).
+pub(crate) struct Target<'a, P> {
+   pub(crate) buffer: &'a mut [P],
+   pub(crate) z_buffer: &'a mut [f32],
+   pub(crate) pattern: &'a [(i32, i32)],
+   pub(crate) x0: i32,
+   pub(crate) y0: i32,
+   pub(crate) x1: i32,
+   pub(crate) y1: i32,
+}
+
+// Lo que guarda cada muestra del framebuffer: un color de 8 bits, o con HDR
+// luz lineal en punto flotante (`Vec3`), cada uno en sus propios buffers
+pub trait Pixel: Copy + Send + Sync {
+   // Mezclar `other` encima con la opacidad dada
+   fn blend(self, other: Self, alpha: f32) -> Self;
+   // Colores y profundidades de las muestras donde se rasterizan los triángulos
+   fn samples(framebuffer: &Framebuffer) -> (&[Self], &[f32]);
+   fn samples_mut(framebuffer: &mut Framebuffer) -> (&mut [Self], &mut [f32]);
+}
+
+impl Pixel for Color {
+   fn blend(self, other: Self, alpha: f32) -> Self {
+      Color::blend(self, other, alpha)
+   }
+
+   fn samples(framebuffer: &Framebuffer) -> (&[Self], &[f32]) {
+      framebuffer.samples()
+   }
+
+   fn samples_mut(framebuffer: &mut Framebuffer) -> (&mut [Self], &mut [f32]) {
+      framebuffer.samples_mut()
    }
-   if vertices[0].1 > vertices[2].1 {
-      vertices.swap(0, 2);
+}
+
+impl Pixel for Vec3 {
+   fn blend(self, other: Self, alpha: f32) -> Self {
+      self + (other - self) * alpha
+   }
+
+   fn samples(framebuffer: &Framebuffer) -> (&[Self], &[f32]) {
+      framebuffer.hdr_samples()
    }
-   if vertices[1].1 > vertices[2].1 {
-      vertices.swap(1, 2);
+
+   fn samples_mut(framebuffer: &mut Framebuffer) -> (&mut [Self], &mut [f32]) {
+      framebuffer.hdr_samples_mut()
    }
-   
-   // Desempaquetar vértices ordenados
-   let (x0, y0, z0) = vertices[0];
-   let (x1, y1, z1) = vertices[1];
-   let (x2, y2, z2) = vertices[2];
-   
-   // Calcular pendientes inversas para interpolación
-   let total_height = y2 - y0;
-   
-   if total_height == 0 {
+}
+
+// Rasterizar un triángulo llamando a `shade` por cada pixel visible con su
+// profundidad, sus coordenadas baricéntricas y los atributos interpolados.
+// Se dibujan los pixeles cuyo centro cae dentro del triángulo. Con `opacity`
+// menor a 1 el color se mezcla con lo que ya está dibujado y no se escribe en
+// el z-buffer. Si el framebuffer tiene MSAA se dibuja en sus muestras (se
+// prueba cada una y se sombrea una vez por pixel) y hay que llamar a
+// `Framebuffer::resolve` para ver el resultado. Si `shade` devuelve `Vec3` se
+// dibuja en el buffer HDR (ver `Framebuffer::set_hdr`).
+pub fn draw_triangle<P, F>(
+   framebuffer: &mut Framebuffer,
+   v0: &Vertex,
+   v1: &Vertex,
+   v2: &Vertex,
+   opacity: f32,
+   shade: F,
+) where
+   P: Pixel,
+   F: FnMut(&Fragment) -> P,
+{
+   let pattern = framebuffer.msaa.pattern();
+   let (x1, y1) = (framebuffer.width as i32, framebuffer.height as i32);
+   let (buffer, z_buffer) = P::samples_mut(framebuffer);
+   let target = Target { buffer, z_buffer, pattern, x0: 0, y0: 0, x1, y1 };
+   rasterize(target, v0, v1, v2, opacity, shade);
+}
+
+// Igual que `draw_triangle`, pero solo sobre los pixeles de `target`. Cada
+// pixel recibe exactamente el mismo valor que al dibujar el framebuffer
+// completo, así que se puede repartir la pantalla en tiles.
+pub(crate) fn rasterize<P, F>(
+   target: Target<P>,
+   v0: &Vertex,
+   v1: &Vertex,
+   v2: &Vertex,
+   opacity: f32,
+   mut shade: F,
+) where
+   P: Pixel,
+   F: FnMut(&Fragment) -> P,
+{
+   // Posiciones en punto fijo
+   let p0 = (to_fixed(v0.position.x), to_fixed(v0.position.y));
+   let mut p1 = (to_fixed(v1.position.x), to_fixed(v1.position.y));
+   let mut p2 = (to_fixed(v2.position.x), to_fixed(v2.position.y));
+
+   // Doble del área con signo; se ordenan los vértices para que sea positiva
+   let mut area = (p1.0 - p0.0) * (p2.1 - p0.1) - (p1.1 - p0.1) * (p2.0 - p0.0);
+   if area == 0 {
       return;
    }
-   
-   // Primera mitad del triángulo (parte superior)
-   let segment_height = y1 - y0;
-   for y in y0..=y1 {
-      if segment_height == 0 {
-         continue;
-      }
-      
-      let alpha = (y - y0) as f32 / total_height as f32;
-      let beta = (y - y0) as f32 / segment_height as f32;
-      
-      let x_a = x0 as f32 + (x2 - x0) as f32 * alpha;
-      let x_b = x0 as f32 + (x1 - x0) as f32 * beta;
-      
-      // Interpolación de z
-      let z_a = z0 + (z2 - z0) * alpha;
-      let z_b = z0 + (z1 - z0) * beta;
-      
-      let start_x = x_a.min(x_b) as i32;
-      let end_x = x_a.max(x_b) as i32;
-      
-      for x in start_x..=end_x {
-         // Interpolar z para este pixel
-         let t = if end_x == start_x { 0.0 } else { (x - start_x) as f32 / (end_x - start_x) as f32 };
-         let z = z_a * (1.0 - t) + z_b * t;
-         
-         // Verificar si este pixel está más cerca que lo que ya está en el z-buffer
-         if x >= 0 && x < framebuffer.width as i32 && y >= 0 && y < framebuffer.height as i32 {
-               let index = y as usize * framebuffer.width + x as usize;
-               if z < framebuffer.z_buffer[index] {
-                  framebuffer.buffer[index] = color;
-                  framebuffer.z_buffer[index] = z;
+   let (v1, v2) = if area < 0 {
+      std::mem::swap(&mut p1, &mut p2);
+      area = -area;
+      (v2, v1)
+   } else {
+      (v1, v2)
+   };
+
+   // Cada arista da el peso del vértice opuesto
+   let edges = [EdgeFunction::new(p1, p2), EdgeFunction::new(p2, p0), EdgeFunction::new(p0, p1)];
+
+   // Rectángulo envolvente, recortado a la zona
+   let (min_x, min_y, max_x, max_y) = fixed_bounds([p0, p1, p2]);
+   let min_x = min_x.max(target.x0 as i64);
+   let min_y = min_y.max(target.y0 as i64);
+   let max_x = max_x.min(target.x1 as i64 - 1);
+   let max_y = max_y.min(target.y1 as i64 - 1);
+   if min_x > max_x || min_y > max_y {
+      return;
+   }
+
+   // Las funciones de arista son lineales: se evalúan en el centro del primer
+   // pixel y se avanzan sumando un paso por columna y por fila. Cada muestra
+   // está a una distancia fija del centro, así que su valor es el del centro
+   // más una constante por arista. Los vértices están en 1/256 de pixel y el
+   // patrón de Direct3D en 1/16, por eso el desplazamiento se pasa de una
+   // escala a la otra con SUBPIXEL / 16.
+   let half = SUBPIXEL / 2;
+   let mut row = edges.each_ref().map(|e| e.at(min_x * SUBPIXEL + half, min_y * SUBPIXEL + half));
+   let samples = target.pattern.len().min(MAX_SAMPLES);
+   let mut offsets = [[0; 3]; MAX_SAMPLES];
+   for (offset, &(ox, oy)) in offsets.iter_mut().zip(target.pattern) {
+      *offset = edges.each_ref().map(|e| (e.a * ox as i64 + e.b * oy as i64) * SUBPIXEL / 16);
+   }
+   let all_samples = (1u32 << samples) - 1;
+   let area = area as f32;
+   let stride = (target.x1 - target.x0) as usize;
+
+   for y in min_y..=max_y {
+      let mut w = row;
+      for x in min_x..=max_x {
+         let index = (y - target.y0 as i64) as usize * stride + (x - target.x0 as i64) as usize;
+         let first = index * samples;
+
+         // Muestras dentro del triángulo (`covered`) y, de ellas, las que
+         // están más cerca que lo que ya hay en el z-buffer (`visible`)
+         let mut covered = 0u32;
+         let mut visible = 0u32;
+         let mut depths = [0.0; MAX_SAMPLES];
+         let mut weight_sum = [0; 3];
+         for (s, offset) in offsets[..samples].iter().enumerate() {
+            let ws = [w[0] + offset[0], w[1] + offset[1], w[2] + offset[2]];
+            if edges.iter().zip(&ws).all(|(e, w)| w + e.bias >= 0) {
+               // Baricéntricas en pantalla: la profundidad se interpola así
+               let z = ws[0] as f32 / area * v0.position.z + ws[1] as f32 / area * v1.position.z + ws[2] as f32 / area * v2.position.z;
+               covered |= 1 << s;
+               depths[s] = z;
+               if z < target.z_buffer[first + s] {
+                  visible |= 1 << s;
                }
+               for (sum, w) in weight_sum.iter_mut().zip(ws) {
+                  *sum += w;
+               }
+            }
          }
-      }
-   }
-   
-   // Segunda mitad del triángulo (parte inferior)
-   let segment_height = y2 - y1;
-   for y in y1..=y2 {
-      if segment_height == 0 {
-         continue;
-      }
-      
-      let alpha = (y - y0) as f32 / total_height as f32;
-      let beta = (y - y1) as f32 / segment_height as f32;
-      
-      let x_a = x0 as f32 + (x2 - x0) as f32 * alpha;
-      let x_b = x1 as f32 + (x2 - x1) as f32 * beta;
-      
-      // Interpolación de z
-      let z_a = z0 + (z2 - z0) * alpha;
-      let z_b = z1 + (z2 - z1) * beta;
-      
-      let start_x = x_a.min(x_b) as i32;
-      let end_x = x_a.max(x_b) as i32;
-      
-      for x in start_x..=end_x {
-         // Interpolar z para este pixel
-         let t = if end_x == start_x { 0.0 } else { (x - start_x) as f32 / (end_x - start_x) as f32 };
-         let z = z_a * (1.0 - t) + z_b * t;
-         
-         // Verificar si este pixel está más cerca que lo que ya está en el z-buffer
-         if x >= 0 && x < framebuffer.width as i32 && y >= 0 && y < framebuffer.height as i32 {
-               let index = y as usize * framebuffer.width + x as usize;
-               if z < framebuffer.z_buffer[index] {
-                  framebuffer.buffer[index] = color;
-                  framebuffer.z_buffer[index] = z;
+
+         if visible != 0 {
+            // Se sombrea en el centro del pixel si el triángulo lo cubre
+            // entero, y si no en el centro de las muestras cubiertas, que
+            // siempre está dentro del triángulo
+            let (b0, b1, b2) = if covered == all_samples {
+               (w[0] as f32 / area, w[1] as f32 / area, w[2] as f32 / area)
+            } else {
+               let count = covered.count_ones() as f32 * area;
+               (weight_sum[0] as f32 / count, weight_sum[1] as f32 / count, weight_sum[2] as f32 / count)
+            };
+            let z = b0 * v0.position.z + b1 * v1.position.z + b2 * v2.position.z;
+
+            // Los atributos se interpolan con corrección de perspectiva
+            let p0 = b0 * v0.inv_w;
+            let p1 = b1 * v1.inv_w;
+            let p2 = b2 * v2.inv_w;
+            let sum = p0 + p1 + p2;
+            let barycentric = if sum > 0.0 { Vec3::new(p0, p1, p2) / sum } else { Vec3::new(b0, b1, b2) };
+
+            let fragment = Fragment {
+               x: x as i32,
+               y: y as i32,
+               depth: z,
+               barycentric,
+               varyings: Varyings::weighted(&v0.varyings, &v1.varyings, &v2.varyings, barycentric.x, barycentric.y, barycentric.z),
+            };
+            let color = shade(&fragment);
+            for s in (0..samples).filter(|s| visible & (1 << s) != 0) {
+               if opacity < 1.0 {
+                  target.buffer[first + s] = target.buffer[first + s].blend(color, opacity);
+               } else {
+                  target.buffer[first + s] = color;
+                  target.z_buffer[first + s] = depths[s];
                }
+            }
+         }
+         for (w, e) in w.iter_mut().zip(&edges) {
+            *w += e.a * SUBPIXEL;
          }
       }
+      for (row, e) in row.iter_mut().zip(&edges) {
+         *row += e.b * SUBPIXEL;
+      }
    }
-}
\ No newline at end of file
+}
@@ -0,0 +1,445 @@
+// Ventana de SDL: el ciclo de eventos, las teclas y el panel de
+// estadísticas. Solo se compila con la opción `sdl`; sin ella el programa
+// únicamente renderiza a archivos con --output.
+
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{KeyboardState, Keycode, Scancode};
+use std::time::{Duration, Instant};
+
+use space_ship_render::clock::FrameClock;
+use space_ship_render::color::Color;
+use space_ship_render::font::{draw_text, text_height, text_width};
+use space_ship_render::framebuffer::Framebuffer;
+use space_ship_render::hdr::ToneMapping;
+use space_ship_render::obj_loader::Model;
+use space_ship_render::renderer::{Part, Renderer};
+use space_ship_render::shading::ShadingMode;
+use space_ship_render::texture::Filter;
+use glm::Vec3;
+
+use crate::options::Options;
+use crate::presenter::Presenter;
+use crate::{clear, frame_camera, initial_part, load_app_state, render, AppState, Effect};
+
+// Colores que se recorren con la tecla C sobre la parte seleccionada
+const PART_COLORS: [Color; 4] = [
+    Color { r: 200, g: 60, b: 60 },
+    Color { r: 230, g: 160, b: 40 },
+    Color { r: 70, g: 170, b: 90 },
+    Color { r: 90, g: 120, b: 210 },
+];
+
+impl Effect {
+    fn next(self) -> Self {
+        match self {
+            Effect::Standard => Effect::Toon,
+            Effect::Toon => Effect::Normals,
+            Effect::Normals => Effect::Hologram,
+            Effect::Hologram => Effect::Standard,
+        }
+    }
+    
+    fn name(self) -> &'static str {
+        match self {
+            Effect::Standard => "estándar",
+            Effect::Toon => "caricatura",
+            Effect::Normals => "normales",
+            Effect::Hologram => "holograma",
+        }
+    }
+}
+
+// Cuánto cambia la exposición cada vez que se pulsa + o -, en pasos (EV)
+const EXPOSURE_STEP: f32 = 0.5;
+
+// Duración del destello de daño (tecla K), en segundos
+const FLASH_DURATION: f32 = 0.35;
+
+// Cuadros por segundo de la ventana
+const TARGET_FPS: f32 = 60.0;
+// Velocidad máxima de giro (radianes por segundo) y de desplazamiento
+// (unidades por segundo) con las teclas mantenidas
+const ROTATION_SPEED: f32 = 2.0;
+const MOVE_SPEED: f32 = 1.5;
+// Qué tan rápido la velocidad alcanza la pedida por las teclas: cada segundo
+// queda exp(-RESPONSE) de la diferencia
+const RESPONSE: f32 = 10.0;
+
+fn init(options: &Options) -> Result<(sdl2::Sdl, sdl2::render::Canvas<sdl2::video::Window>, AppState), String> {
+    let sdl_context = sdl2::init()?;
+    let video_subsystem = sdl_context.video()?;
+    
+    let window = video_subsystem.window("Software Renderer", options.width as u32, options.height as u32)
+        .position_centered()
+        .resizable()
+        .build()
+        .map_err(|e| e.to_string())?;
+    
+    let canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+    let app_state = load_app_state(options)?;
+    
+    Ok((sdl_context, canvas, app_state))
+}
+
+// Cambiar la resolución de render, por ejemplo al redimensionar la ventana
+fn resize(app_state: &mut AppState, width: usize, height: usize) {
+    // Al minimizar la ventana el tamaño puede llegar a cero
+    if width == 0 || height == 0 {
+        return;
+    }
+    app_state.framebuffer.resize(width, height);
+    frame_camera(&mut app_state.renderer.camera, width, height);
+}
+
+fn set_color(app_state: &mut AppState, color: Color) {
+    app_state.current_color = color;
+}
+
+fn render_buffer(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>, presenter: &mut Presenter, framebuffer: &Framebuffer) -> Result<(), String> {
+    presenter.present(canvas, framebuffer)
+}
+
+// Parte que sigue a la elegida al pulsar Tab: cada objeto y después, si tiene
+// más de uno, cada uno de sus grupos
+fn next_part(model: &Model, (object, group): (usize, Option<usize>)) -> (usize, Option<usize>) {
+    let groups = model.objects[object].groups.len();
+    let next_group = group.map_or(0, |g| g + 1);
+    if groups > 1 && next_group < groups {
+        (object, Some(next_group))
+    } else {
+        ((object + 1) % model.objects.len(), None)
+    }
+}
+
+fn part_name(model: &Model, (object, group): (usize, Option<usize>)) -> String {
+    let object = &model.objects[object];
+    match group.map(|g| &object.groups[g].name) {
+        Some(name) if name.is_empty() => format!("{} (grupo sin nombre)", object.name),
+        Some(name) => format!("{} / {}", object.name, name),
+        None => object.name.clone(),
+    }
+}
+
+// Estado de la parte elegida, que puede ser un objeto o uno de sus grupos
+fn selected_part(app_state: &mut AppState) -> Option<&mut Part> {
+    let (object, group) = app_state.selected_part;
+    let part = app_state.renderer.parts.get_mut(object)?;
+    match group {
+        Some(group) => part.groups.get_mut(group),
+        None => Some(part),
+    }
+}
+
+// Curva de tono en uso, o que no se usa HDR
+fn hdr_name(renderer: &Renderer) -> &'static str {
+    if renderer.hdr { renderer.tone_mapper.tone_mapping.name() } else { "desactivado" }
+}
+
+// Escribir sobre el cuadro los FPS, el costo de dibujarlo, los triángulos y
+// el estado actual de la nave
+fn draw_hud(app_state: &mut AppState, clock: &FrameClock) {
+    let angles = app_state.transform.rotation.to_euler();
+    // Efectos encendidos en el orden en que se aplican
+    let enabled: Vec<_> = app_state.post.passes.iter()
+        .filter(|pass| pass.enabled)
+        .map(|pass| pass.effect.name())
+        .collect();
+    let post = if enabled.is_empty() { "ninguno".to_string() } else { enabled.join(" > ") };
+    let selected = &app_state.post.passes[app_state.selected_pass];
+    let text = format!(
+        "{:.0} FPS - cuadro {:.1} ms - render {:.1} ms\n\
+         Modelo: {} vértices, {} caras\n\
+         Triángulos: {} dibujados, {} descartados\n\
+         Ángulos: X {:.0}  Y {:.0}  Z {:.0}\n\
+         Sombreado: {} - efecto {} - modo {} - {}\n\
+         HDR: {} - exposición {:+.1} EV\n\
+         Post-proceso: {} - elegido {} ({})",
+        clock.fps(),
+        clock.delta() * 1000.0,
+        app_state.render_time.as_secs_f64() * 1000.0,
+        app_state.model.vertices.len(),
+        app_state.model.faces.len(),
+        app_state.stats.triangles_drawn,
+        app_state.stats.triangles_culled,
+        angles.x.to_degrees(),
+        angles.y.to_degrees(),
+        angles.z.to_degrees(),
+        app_state.renderer.shading_mode.name(),
+        app_state.effect.name(),
+        app_state.renderer.render_mode.name(),
+        app_state.renderer.msaa.name(),
+        hdr_name(&app_state.renderer),
+        app_state.renderer.tone_mapper.exposure,
+        post,
+        selected.effect.name(),
+        if selected.enabled { "encendido" } else { "apagado" },
+    );
+    
+    // Letras más grandes en pantallas grandes, sobre un fondo oscuro
+    // semitransparente para que se lean encima de la nave
+    let framebuffer = &mut app_state.framebuffer;
+    let scale = (framebuffer.height / 300).max(1);
+    let margin = 4 * scale;
+    framebuffer.fill_rect(
+        margin as i32,
+        margin as i32,
+        text_width(&text, scale) + 2 * margin,
+        text_height(&text, scale) + 2 * margin,
+        Color::new(0, 0, 0),
+        0.6,
+    );
+    draw_text(framebuffer, 2 * margin as i32, 2 * margin as i32, &text, Color::new(230, 230, 230), scale);
+}
+
+// Avanzar la animación `dt` segundos. El movimiento de la nave sale de las
+// teclas que están apretadas en este momento, no de los eventos de repetición,
+// así que la velocidad no depende del teclado ni de la máquina.
+fn update(app_state: &mut AppState, keyboard: &KeyboardState, dt: f32) {
+    let axis = |positive: Scancode, negative: Scancode| {
+        keyboard.is_scancode_pressed(positive) as i32 as f32 - keyboard.is_scancode_pressed(negative) as i32 as f32
+    };
+    
+    // Flechas: girar alrededor de los ejes de la pantalla, sin importar la
+    // orientación actual de la nave; Z y X: alabeo. WASD y QE: desplazar.
+    let angular = Vec3::new(
+        axis(Scancode::Down, Scancode::Up),
+        axis(Scancode::Left, Scancode::Right),
+        axis(Scancode::Z, Scancode::X),
+    ) * ROTATION_SPEED;
+    let linear = Vec3::new(
+        axis(Scancode::D, Scancode::A),
+        axis(Scancode::W, Scancode::S),
+        axis(Scancode::Q, Scancode::E),
+    ) * MOVE_SPEED;
+    
+    // La velocidad se acerca a la pedida en lugar de saltar, para que la nave
+    // arranque y frene con suavidad
+    let t = 1.0 - (-RESPONSE * dt).exp();
+    let motion = &mut app_state.motion;
+    motion.angular = motion.angular + (angular - motion.angular) * t;
+    motion.linear = motion.linear + (linear - motion.linear) * t;
+    
+    // Los ejes salen de la cámara, así que siguen a la pantalla aunque se
+    // haya girado la vista con el mouse. Z apunta hacia la cámara.
+    let (right, up, forward) = app_state.renderer.camera.basis();
+    let back = forward * -1.0;
+    let transform = &mut app_state.transform;
+    transform.rotate(right, motion.angular.x * dt);
+    transform.rotate(up, motion.angular.y * dt);
+    transform.rotate(back, motion.angular.z * dt);
+    transform.translation = transform.translation + (right * motion.linear.x + up * motion.linear.y + back * motion.linear.z) * dt;
+    
+    app_state.flash = (app_state.flash - dt / FLASH_DURATION).max(0.0);
+    
+    // Acercar la cámara suavemente a donde la dejó el mouse
+    app_state.orbit.update(dt);
+    app_state.orbit.apply(&mut app_state.renderer.camera);
+}
+
+fn handle_keys(app_state: &mut AppState, keycode: Keycode) {
+    match keycode {
+        Keycode::Num1 | Keycode::Num2 | Keycode::Num3 => {
+            // Cambiar el modo de sombreado
+            app_state.renderer.shading_mode = match keycode {
+                Keycode::Num1 => ShadingMode::Flat,
+                Keycode::Num2 => ShadingMode::Gouraud,
+                _ => ShadingMode::Phong,
+            };
+            println!("Modo de sombreado: {}", app_state.renderer.shading_mode.name());
+        },
+        Keycode::M => {
+            // Cambiar entre caras, aristas o ambas
+            app_state.renderer.render_mode = app_state.renderer.render_mode.next();
+            println!("Modo de dibujo: {}", app_state.renderer.render_mode.name());
+        },
+        Keycode::L => {
+            // Activar o desactivar el suavizado de las aristas
+            let style = &mut app_state.renderer.line_style;
+            style.antialiased = !style.antialiased;
+            println!("Suavizado de líneas: {}", if style.antialiased { "activado" } else { "desactivado" });
+        },
+        Keycode::N => {
+            // Cambiar la cantidad de muestras por pixel
+            app_state.renderer.msaa = app_state.renderer.msaa.next();
+            println!("Antialiasing: {}", app_state.renderer.msaa.name());
+        },
+        Keycode::P => {
+            // Elegir el siguiente efecto de post-proceso
+            app_state.selected_pass = (app_state.selected_pass + 1) % app_state.post.passes.len();
+            let pass = &app_state.post.passes[app_state.selected_pass];
+            println!("Post-proceso elegido: {} ({})", pass.effect.name(), if pass.enabled { "encendido" } else { "apagado" });
+        },
+        Keycode::O => {
+            // Encender o apagar el efecto elegido
+            let pass = &mut app_state.post.passes[app_state.selected_pass];
+            pass.enabled = !pass.enabled;
+            println!("{}: {}", pass.effect.name(), if pass.enabled { "encendido" } else { "apagado" });
+        },
+        Keycode::U => {
+            // Aplicar el efecto elegido un lugar antes en la cadena
+            app_state.selected_pass = app_state.post.move_earlier(app_state.selected_pass);
+            let order: Vec<_> = app_state.post.passes.iter().map(|pass| pass.effect.name()).collect();
+            println!("Orden del post-proceso: {}", order.join(" > "));
+        },
+        Keycode::G => {
+            // Recorrer: sin HDR, Reinhard y ACES
+            let renderer = &mut app_state.renderer;
+            match (renderer.hdr, renderer.tone_mapper.tone_mapping) {
+                (false, _) => (renderer.hdr, renderer.tone_mapper.tone_mapping) = (true, ToneMapping::Reinhard),
+                (true, ToneMapping::Reinhard) => renderer.tone_mapper.tone_mapping = ToneMapping::Aces,
+                (true, ToneMapping::Aces) => renderer.hdr = false,
+            }
+            println!("HDR: {}", hdr_name(renderer));
+        },
+        Keycode::Plus | Keycode::Equals | Keycode::Minus => {
+            // Subir o bajar la exposición (solo se nota con HDR)
+            let step = if keycode == Keycode::Minus { -EXPOSURE_STEP } else { EXPOSURE_STEP };
+            app_state.renderer.tone_mapper.exposure += step;
+            println!("Exposición: {:+.1} EV", app_state.renderer.tone_mapper.exposure);
+        },
+        Keycode::T => {
+            // Activar o desactivar la textura
+            app_state.renderer.texturing = !app_state.renderer.texturing;
+            println!("Textura: {}", if app_state.renderer.texturing { "activada" } else { "desactivada" });
+        },
+        Keycode::V => {
+            // Cambiar el efecto de pixel
+            app_state.effect = app_state.effect.next();
+            println!("Efecto: {}", app_state.effect.name());
+        },
+        Keycode::I => {
+            // Mostrar u ocultar el panel de estadísticas
+            app_state.show_hud = !app_state.show_hud;
+        },
+        Keycode::K => {
+            // Destello rojo, como al recibir un impacto
+            app_state.flash = 1.0;
+        },
+        Keycode::B => {
+            // Alternar entre filtro más cercano y bilineal
+            if let Some(texture) = app_state.renderer.texture.as_mut() {
+                texture.filter = match texture.filter {
+                    Filter::Nearest => Filter::Bilinear,
+                    Filter::Bilinear => Filter::Nearest,
+                };
+                println!("Filtro de textura: {}", texture.filter.name());
+            }
+        },
+        Keycode::Tab if !app_state.model.objects.is_empty() => {
+            // Seleccionar la siguiente parte del modelo
+            app_state.selected_part = next_part(&app_state.model, app_state.selected_part);
+            println!("Parte seleccionada: {}", part_name(&app_state.model, app_state.selected_part));
+        },
+        Keycode::H => {
+            // Ocultar o mostrar la parte seleccionada
+            if let Some(part) = selected_part(app_state) {
+                part.visible = !part.visible;
+            }
+        },
+        Keycode::C => {
+            // Pintar la parte seleccionada con el siguiente color de la paleta;
+            // después del último vuelve a su color original
+            let original = match app_state.selected_part {
+                (object, None) => app_state.model.objects.get(object).and_then(|o| initial_part(o).color),
+                (_, Some(_)) => None,
+            };
+            if let Some(part) = selected_part(app_state) {
+                let next = match part.color.and_then(|c| PART_COLORS.iter().position(|&p| p == c)) {
+                    Some(i) => i + 1,
+                    None => 0,
+                };
+                part.color = PART_COLORS.get(next).copied().or(original);
+            }
+        },
+        Keycode::F => {
+            // Girar 180 grados en Y (invertir dirección)
+            app_state.transform.rotate(Vec3::new(0.0, 1.0, 0.0), std::f32::consts::PI);
+        },
+        Keycode::R => {
+            // Volver a la posición y orientación del arranque (las de
+            // --angle-x y --angle-y), no a rotación cero
+            app_state.transform = app_state.initial_transform;
+            app_state.orbit.reset();
+        },
+        _ => {}
+    }
+}
+
+
+// Abrir la ventana y dibujar la nave hasta que se cierre
+pub fn run(options: &Options) -> Result<(), String> {
+    let (sdl_context, mut canvas, mut app_state) = init(options)?;
+    let mut event_pump = sdl_context.event_pump()?;
+    
+    // La textura de la ventana se reutiliza entre cuadros
+    let texture_creator = canvas.texture_creator();
+    let mut presenter = Presenter::new(&texture_creator, options.present_format, app_state.framebuffer.width, app_state.framebuffer.height)?;
+    let mut last_report = Instant::now();
+    let mut clock = FrameClock::new(TARGET_FPS);
+    
+    let mut running = true;
+    while running {
+        let dt = clock.tick();
+        
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. } |
+                Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
+                    running = false;
+                },
+                Event::KeyDown { keycode: Some(keycode), .. } => {
+                    handle_keys(&mut app_state, keycode);
+                },
+                Event::MouseMotion { mousestate, xrel, yrel, .. } => {
+                    // Arrastrar gira la cámara alrededor de la nave; con el
+                    // botón del medio (o el derecho) la desplaza
+                    if mousestate.left() {
+                        app_state.orbit.rotate(xrel as f32, yrel as f32);
+                    } else if mousestate.middle() || mousestate.right() {
+                        let (_, height) = canvas.window().size();
+                        app_state.orbit.pan(xrel as f32, yrel as f32, height as usize, app_state.renderer.camera.fov);
+                    }
+                },
+                Event::MouseWheel { y, .. } => {
+                    app_state.orbit.zoom(y as f32);
+                },
+                Event::Window { win_event: WindowEvent::SizeChanged(..), .. } => {
+                    // Renderizar a la resolución real de la ventana, que en
+                    // pantallas de alta densidad puede ser mayor que la lógica
+                    let (width, height) = canvas.output_size()?;
+                    resize(&mut app_state, width as usize, height as usize);
+                },
+                _ => {}
+            }
+        }
+        
+        update(&mut app_state, &event_pump.keyboard_state(), dt);
+        
+        clear(&mut app_state);
+        
+        set_color(&mut app_state, Color::new(255, 255, 0));
+        render(&mut app_state);
+        if app_state.show_hud {
+            draw_hud(&mut app_state, &clock);
+        }
+        
+        render_buffer(&mut canvas, &mut presenter, &app_state.framebuffer)?;
+        
+        // Mostrar en el título cuánto cuesta subir el cuadro a la pantalla
+        if last_report.elapsed() >= Duration::from_secs(1) {
+            let title = format!(
+                "Software Renderer - {:.0} FPS - subida {}: {:.2} ms",
+                clock.fps(),
+                presenter.format().name(),
+                presenter.average_upload_time().as_secs_f64() * 1000.0,
+            );
+            canvas.window_mut().set_title(&title).map_err(|e| e.to_string())?;
+            last_report = Instant::now();
+        }
+        
+        // Limitar FPS: esperar solo lo que falte del cuadro
+        clock.wait();
+    }
+    
+    Ok(())
+}
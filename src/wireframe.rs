@@ -0,0 +1,131 @@
+use glm::Vec3;
+use crate::line::LineStyle;
+
+// Desplazamiento de profundidad de las aristas hacia la cámara, como
+// glPolygonOffset: una parte fija y otra proporcional a la pendiente en
+// pantalla de sus caras, para que una arista no quede tapada por su propia
+// cara en los pixeles que caen un poco fuera de ella. El tope evita que las
+// caras vistas casi de canto dejen pasar aristas que están detrás de otras.
+const OFFSET_UNITS: f32 = 1e-5;
+const OFFSET_FACTOR: f32 = 1.0;
+const MAX_OFFSET: f32 = 5e-4;
+
+// Qué se dibuja del modelo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+   // Solo las caras sombreadas
+   Solid,
+   // Todas las aristas, también las de atrás, sin rellenar las caras
+   Wireframe,
+   // Caras sombreadas y encima sus aristas visibles
+   SolidWireframe,
+   // Solo las aristas visibles: las caras tapan lo que tienen detrás pero no
+   // se pintan
+   HiddenLine,
+}
+
+impl RenderMode {
+   pub fn name(self) -> &'static str {
+      match self {
+         RenderMode::Solid => "sólido",
+         RenderMode::Wireframe => "alambre",
+         RenderMode::SolidWireframe => "sólido + alambre",
+         RenderMode::HiddenLine => "líneas ocultas",
+      }
+   }
+
+   pub fn next(self) -> Self {
+      match self {
+         RenderMode::Solid => RenderMode::Wireframe,
+         RenderMode::Wireframe => RenderMode::SolidWireframe,
+         RenderMode::SolidWireframe => RenderMode::HiddenLine,
+         RenderMode::HiddenLine => RenderMode::Solid,
+      }
+   }
+
+   // Si se rasterizan las caras (aunque sea solo su profundidad)
+   pub fn draws_faces(self) -> bool {
+      self != RenderMode::Wireframe
+   }
+
+   // Si se dibujan las aristas
+   pub fn draws_edges(self) -> bool {
+      self != RenderMode::Solid
+   }
+}
+
+// Arista entre dos vértices del modelo (a < b). `front` indica si alguna de
+// las caras que la comparten mira hacia la cámara y `slope` es la mayor
+// pendiente de profundidad de esas caras (ver `depth_slope`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+   pub a: usize,
+   pub b: usize,
+   pub front: bool,
+   pub slope: f32,
+}
+
+// Cara para `unique_edges`: índices de sus vértices, si mira a la cámara y
+// su pendiente de profundidad
+pub type EdgeFace = ([usize; 3], bool, f32);
+
+// Aristas de una lista de triángulos, cada una una sola vez aunque la
+// compartan varias caras
+pub fn unique_edges<I: IntoIterator<Item = EdgeFace>>(faces: I) -> Vec<Edge> {
+   let mut edges: Vec<Edge> = faces.into_iter()
+      .flat_map(|([v0, v1, v2], front, slope)| {
+         let slope = if front { slope } else { 0.0 };
+         [(v0, v1), (v1, v2), (v2, v0)].map(|(a, b)| Edge { a: a.min(b), b: a.max(b), front, slope })
+      })
+      .filter(|edge| edge.a != edge.b)
+      .collect();
+
+   // Al ordenar, las copias de una arista quedan juntas
+   edges.sort_unstable_by_key(|edge| (edge.a, edge.b));
+   edges.dedup_by(|next, kept| {
+      let same = next.a == kept.a && next.b == kept.b;
+      if same {
+         kept.front |= next.front;
+         kept.slope = kept.slope.max(next.slope);
+      }
+      same
+   });
+   edges
+}
+
+// Cuánto cambia por pixel la profundidad de un triángulo en pantalla: el
+// mayor de |dz/dx| y |dz/dy| de su plano. Un triángulo visto de canto
+// devuelve infinito.
+pub fn depth_slope(p0: Vec3, p1: Vec3, p2: Vec3) -> f32 {
+   let (e1, e2) = (p1 - p0, p2 - p0);
+   let det = e1.x * e2.y - e2.x * e1.y;
+   if det == 0.0 {
+      return f32::INFINITY;
+   }
+   let dz_dx = (e1.z * e2.y - e2.z * e1.y) / det;
+   let dz_dy = (e1.x * e2.z - e2.x * e1.z) / det;
+   dz_dx.abs().max(dz_dy.abs())
+}
+
+// Cuánto adelantar hacia la cámara una arista con la pendiente dada (la de
+// `Edge`) para probarla contra el z-buffer. Crece con lo que la línea se
+// aleja de su eje, porque ahí la cara ya está más lejos o más cerca.
+pub fn edge_offset(slope: f32, style: &LineStyle) -> f32 {
+   (OFFSET_UNITS + OFFSET_FACTOR * slope * style.reach()).min(MAX_OFFSET)
+}
+
+#[cfg(test)]
+mod tests {
+   use super::unique_edges;
+
+   #[test]
+   fn shared_edges_are_drawn_once() {
+      // Dos triángulos de un cuadrado (uno de frente y otro de espaldas) y uno
+      // degenerado: la diagonal compartida aparece una vez y cuenta como de frente
+      let edges = unique_edges([([0, 1, 2], true, 0.5), ([2, 1, 3], false, 9.0), ([4, 4, 5], true, 0.0)]);
+      let pairs: Vec<_> = edges.iter().map(|e| (e.a, e.b, e.front)).collect();
+      assert_eq!(pairs, [(0, 1, true), (0, 2, true), (1, 2, true), (1, 3, false), (2, 3, false), (4, 5, true)]);
+      let diagonal = edges.iter().find(|e| (e.a, e.b) == (1, 2)).unwrap();
+      assert_eq!(diagonal.slope, 0.5);
+   }
+}
@@ -0,0 +1,559 @@
+// Pruebas de regresión con imágenes de referencia: cada escena se renderiza en
+// un Framebuffer y se compara pixel por pixel con su imagen en tests/golden.
+//
+// Si una prueba falla se escriben la imagen obtenida y una imagen de
+// diferencias en target/golden-diff. Para aceptar un cambio intencional en la
+// salida, volver a generar las referencias con:
+//
+//    UPDATE_GOLDEN=1 cargo test
+
+use std::path::{Path, PathBuf};
+use glm::{Vec2, Vec3, Vec4};
+use space_ship_render::camera::Camera;
+use space_ship_render::color::Color;
+use space_ship_render::hdr::{ToneMapper, ToneMapping};
+use space_ship_render::font::{draw_text, text_height, text_width};
+use space_ship_render::image::Image;
+use space_ship_render::light::default_lighting;
+use space_ship_render::msaa::Msaa;
+use space_ship_render::obj_loader::SMOOTHING_ANGLE;
+use space_ship_render::postprocess::{Lut, PostChain, PostEffect, PostPass};
+use space_ship_render::renderer::Part;
+use space_ship_render::shader::NormalShader;
+use space_ship_render::shading::ShadingMode;
+use space_ship_render::line::{draw_line, LineCap, LineStyle};
+use space_ship_render::wireframe::RenderMode;
+use space_ship_render::triangle::{draw_triangle, Fragment, Varyings, Vertex};
+use space_ship_render::{Framebuffer, Model, Renderer, Transform};
+
+// Diferencia máxima permitida por canal antes de considerar distinto un pixel
+const TOLERANCE: u8 = 2;
+
+fn golden_dir() -> PathBuf {
+   Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden")
+}
+
+fn diff_dir() -> PathBuf {
+   Path::new(env!("CARGO_MANIFEST_DIR")).join("target").join("golden-diff")
+}
+
+// Comparar el framebuffer con la imagen de referencia `name`
+fn check(name: &str, framebuffer: &Framebuffer) {
+   let actual = framebuffer.to_image();
+   let golden_path = golden_dir().join(format!("{}.png", name));
+
+   if std::env::var_os("UPDATE_GOLDEN").is_some() {
+      std::fs::create_dir_all(golden_dir()).unwrap();
+      actual.save(&golden_path).unwrap();
+      return;
+   }
+
+   let golden = match Image::load(&golden_path) {
+      Ok(golden) => golden,
+      Err(e) => panic!(
+         "{}: no se pudo leer la imagen de referencia {} ({}); generarla con UPDATE_GOLDEN=1",
+         name, golden_path.display(), e,
+      ),
+   };
+
+   assert!(
+      golden.width == actual.width && golden.height == actual.height,
+      "{}: la referencia mide {}x{} y la imagen obtenida {}x{}",
+      name, golden.width, golden.height, actual.width, actual.height,
+   );
+
+   // Imagen de diferencias: la referencia atenuada en gris y en rojo los
+   // pixeles que se salen de la tolerancia
+   let mut mismatches = 0;
+   let mut worst = 0;
+   let mut diff = Vec::with_capacity(actual.pixels.len());
+   for (a, g) in actual.pixels.iter().zip(&golden.pixels) {
+      let delta = a.r.abs_diff(g.r).max(a.g.abs_diff(g.g)).max(a.b.abs_diff(g.b));
+      worst = worst.max(delta);
+      if delta > TOLERANCE {
+         mismatches += 1;
+         diff.push(Color::new(128 + delta / 2, 0, 0));
+      } else {
+         let gray = ((g.r as u16 + g.g as u16 + g.b as u16) / 12) as u8;
+         diff.push(Color::new(gray, gray, gray));
+      }
+   }
+
+   if mismatches > 0 {
+      std::fs::create_dir_all(diff_dir()).unwrap();
+      let actual_path = diff_dir().join(format!("{}.actual.png", name));
+      let diff_path = diff_dir().join(format!("{}.diff.png", name));
+      actual.save(&actual_path).unwrap();
+      Image { width: actual.width, height: actual.height, pixels: diff }.save(&diff_path).unwrap();
+
+      panic!(
+         "{}: {} pixeles difieren de la referencia (diferencia máxima {}, tolerancia {}); ver {} y {}",
+         name, mismatches, worst, TOLERANCE, actual_path.display(), diff_path.display(),
+      );
+   }
+}
+
+fn vertex(x: f32, y: f32, z: f32, w: f32, color: Vec3, uv: Vec2) -> Vertex {
+   Vertex {
+      position: Vec3::new(x, y, z),
+      inv_w: 1.0 / w,
+      varyings: Varyings {
+         world_position: Vec3::new(x, y, z),
+         normal: Vec3::new(0.0, 0.0, 1.0),
+         color,
+         uv,
+         custom: Vec4::new(0.0, 0.0, 0.0, 0.0),
+      },
+   }
+}
+
+fn plain(x: f32, y: f32, z: f32) -> Vertex {
+   vertex(x, y, z, 1.0, Vec3::new(0.0, 0.0, 0.0), Vec2::new(0.0, 0.0))
+}
+
+fn blank(width: usize, height: usize) -> Framebuffer {
+   let mut framebuffer = Framebuffer::new(width, height);
+   framebuffer.clear(Color::new(0, 0, 0));
+   framebuffer
+}
+
+#[test]
+fn triangle_flat() {
+   let mut framebuffer = blank(64, 64);
+   draw_triangle(&mut framebuffer, &plain(8.0, 4.0, 0.5), &plain(58.0, 30.0, 0.5), &plain(16.0, 60.0, 0.5), 1.0, |_| {
+      Color::new(230, 180, 40)
+   });
+   check("triangle_flat", &framebuffer);
+}
+
+#[test]
+fn triangle_gradient() {
+   let mut framebuffer = blank(64, 64);
+   let uv = Vec2::new(0.0, 0.0);
+   draw_triangle(
+      &mut framebuffer,
+      &vertex(32.0, 2.0, 0.5, 1.0, Vec3::new(1.0, 0.0, 0.0), uv),
+      &vertex(62.0, 60.0, 0.5, 1.0, Vec3::new(0.0, 1.0, 0.0), uv),
+      &vertex(2.0, 50.0, 0.5, 1.0, Vec3::new(0.0, 0.0, 1.0), uv),
+      1.0,
+      |fragment| Color::from_vec3(fragment.varyings.color),
+   );
+   check("triangle_gradient", &framebuffer);
+}
+
+// Un cuadrado inclinado hacia el fondo con un tablero de ajedrez en UV: las
+// casillas lejanas deben verse más chicas (corrección de perspectiva)
+#[test]
+fn quad_perspective_checker() {
+   let mut framebuffer = blank(64, 64);
+   let color = Vec3::new(0.0, 0.0, 0.0);
+   let near_left = vertex(2.0, 62.0, 0.2, 1.0, color, Vec2::new(0.0, 0.0));
+   let near_right = vertex(62.0, 62.0, 0.2, 1.0, color, Vec2::new(1.0, 0.0));
+   let far_right = vertex(44.0, 8.0, 0.8, 4.0, color, Vec2::new(1.0, 1.0));
+   let far_left = vertex(20.0, 8.0, 0.8, 4.0, color, Vec2::new(0.0, 1.0));
+
+   let checker = |fragment: &Fragment| {
+      let uv = fragment.varyings.uv;
+      let cell = (uv.x * 8.0) as i32 + (uv.y * 8.0) as i32;
+      if cell % 2 == 0 { Color::new(240, 240, 240) } else { Color::new(30, 60, 120) }
+   };
+   draw_triangle(&mut framebuffer, &near_left, &near_right, &far_right, 1.0, checker);
+   draw_triangle(&mut framebuffer, &near_left, &far_right, &far_left, 1.0, checker);
+   check("quad_perspective_checker", &framebuffer);
+}
+
+// Dos triángulos que se atraviesan: el segundo está inclinado en profundidad
+// y el z-buffer debe cortarlo donde pasa detrás del primero
+#[test]
+fn triangles_depth_test() {
+   let mut framebuffer = blank(64, 64);
+   draw_triangle(&mut framebuffer, &plain(4.0, 4.0, 0.5), &plain(60.0, 8.0, 0.5), &plain(8.0, 60.0, 0.5), 1.0, |_| {
+      Color::new(200, 40, 40)
+   });
+   draw_triangle(&mut framebuffer, &plain(60.0, 60.0, 0.2), &plain(6.0, 30.0, 0.8), &plain(30.0, 6.0, 0.8), 1.0, |_| {
+      Color::new(40, 200, 40)
+   });
+   check("triangles_depth_test", &framebuffer);
+}
+
+#[test]
+fn triangle_transparent() {
+   let mut framebuffer = blank(64, 64);
+   draw_triangle(&mut framebuffer, &plain(4.0, 4.0, 0.5), &plain(60.0, 4.0, 0.5), &plain(4.0, 60.0, 0.5), 1.0, |_| {
+      Color::new(40, 80, 220)
+   });
+   draw_triangle(&mut framebuffer, &plain(24.0, 8.0, 0.3), &plain(60.0, 56.0, 0.3), &plain(8.0, 56.0, 0.3), 0.5, |_| {
+      Color::new(240, 200, 40)
+   });
+   check("triangle_transparent", &framebuffer);
+}
+
+// Un abanico de triángulos semitransparentes que cubre toda la imagen. Las
+// aristas horizontales, verticales y diagonales que salen del centro pasan
+// justo por centros de pixel, y el resto tiene vértices fraccionarios: con la
+// regla arriba-izquierda cada pixel se dibuja exactamente una vez, sin huecos
+// ni mezclas dobles en las aristas compartidas
+#[test]
+fn fan_covers_each_pixel_once() {
+   let mut framebuffer = blank(64, 64);
+   let center = (32.5, 32.5);
+   let rim = [
+      (0.0, 0.0), (21.6, 0.0), (32.5, 0.0), (64.0, 0.0), (64.0, 17.9), (64.0, 32.5), (64.0, 64.0),
+      (38.8, 64.0), (32.5, 64.0), (0.0, 64.0), (0.0, 32.5), (0.0, 22.75),
+   ];
+   for i in 0..rim.len() {
+      let (ax, ay) = rim[i];
+      let (bx, by) = rim[(i + 1) % rim.len()];
+      draw_triangle(&mut framebuffer, &plain(center.0, center.1, 0.5), &plain(ax, ay, 0.5), &plain(bx, by, 0.5), 0.5, |fragment| {
+         let sum = fragment.barycentric.x + fragment.barycentric.y + fragment.barycentric.z;
+         assert!((sum - 1.0).abs() < 1e-4, "las baricéntricas suman {}", sum);
+         Color::new(255, 255, 255)
+      });
+   }
+
+   let expected = Color::new(0, 0, 0).blend(Color::new(255, 255, 255), 0.5);
+   for (i, pixel) in framebuffer.buffer.iter().enumerate() {
+      assert_eq!(*pixel, expected, "pixel ({}, {}) no se dibujó exactamente una vez", i % 64, i / 64);
+   }
+}
+
+#[test]
+fn text_charset() {
+   // Todo el ASCII imprimible en tamaño normal y doble, sobre un fondo
+   // semitransparente que deja ver una franja de color
+   let charset: String = (' '..='~').collect();
+   let text = format!("{}\n{}\n{}\nTriángulos: 1234", &charset[..32], &charset[32..64], &charset[64..]);
+   let mut framebuffer = blank(text_width(&text, 2) + 8, text_height(&text, 1) + text_height(&text, 2) + 12);
+   framebuffer.fill_rect(0, 0, framebuffer.width / 2, framebuffer.height, Color::new(200, 60, 60), 1.0);
+   framebuffer.fill_rect(2, 2, text_width(&text, 1) + 4, text_height(&text, 1) + 4, Color::new(40, 40, 90), 0.6);
+   draw_text(&mut framebuffer, 4, 4, &text, Color::new(255, 255, 255), 1);
+   draw_text(&mut framebuffer, 4, text_height(&text, 1) as i32 + 8, &text, Color::new(255, 220, 0), 2);
+   check("text_charset", &framebuffer);
+}
+
+#[test]
+fn lines_antialiased() {
+   // Abanico de líneas con extremos fuera de los centros de los pixeles: finas
+   // (Xiaolin Wu), gruesas con los tres tipos de extremo y sin suavizar. La
+   // de abajo pasa por detrás de un rectángulo que está más cerca.
+   let mut framebuffer = blank(160, 120);
+   framebuffer.clear(Color::new(240, 240, 230));
+   let styles = [
+      (0.6, LineCap::Butt, true),
+      (1.0, LineCap::Butt, true),
+      (2.5, LineCap::Butt, true),
+      (4.0, LineCap::Square, true),
+      (6.0, LineCap::Round, true),
+      (3.0, LineCap::Round, false),
+   ];
+   for (i, (width, cap, antialiased)) in styles.into_iter().enumerate() {
+      let angle = 0.15 + i as f32 * 0.25;
+      let center = Vec3::new(24.3, 20.7, 0.5);
+      let end = center + Vec3::new(angle.cos(), angle.sin(), 0.0) * 70.0;
+      let style = LineStyle { color: Color::new(20, 40, 120), width, cap, antialiased };
+      draw_line(&mut framebuffer, center, end, &style, None);
+   }
+
+   for y in 90..110 {
+      for x in 60..100 {
+         framebuffer.z_buffer[y * 160 + x] = 0.4;
+      }
+   }
+   let style = LineStyle { color: Color::new(200, 30, 30), width: 5.0, cap: LineCap::Round, antialiased: true };
+   draw_line(&mut framebuffer, Vec3::new(10.0, 100.0, 0.5), Vec3::new(150.0, 96.0, 0.5), &style, Some(0.0));
+   check("lines_antialiased", &framebuffer);
+}
+
+#[test]
+fn thin_line_subpixel_coverage() {
+   // Una línea de un pixel de ancho sobre el borde entre dos filas cubre media
+   // fila de cada una, y los extremos solo la parte de su columna que alcanzan
+   let style = LineStyle { color: Color::new(0, 0, 0), width: 1.0, cap: LineCap::Butt, antialiased: true };
+   let mut framebuffer = blank(16, 16);
+   framebuffer.clear(Color::new(255, 255, 255));
+   draw_line(&mut framebuffer, Vec3::new(2.25, 10.5, 0.0), Vec3::new(8.5, 10.5, 0.0), &style, None);
+   draw_line(&mut framebuffer, Vec3::new(3.0, 4.0, 0.0), Vec3::new(9.0, 4.0, 0.0), &style, None);
+
+   let value = |x: usize, y: usize| framebuffer.buffer[y * 16 + x].r;
+   let row_10: Vec<u8> = (1..10).map(|x| value(x, 10)).collect();
+   assert_eq!(row_10, [255, 64, 0, 0, 0, 0, 0, 128, 255]);
+   assert!((0..16).all(|x| value(x, 9) == 255 || (3..9).contains(&x)), "la línea de la fila 10 no debe tocar la 9 fuera de la otra línea");
+   let rows_3_4: Vec<(u8, u8)> = (2..10).map(|x| (value(x, 3), value(x, 4))).collect();
+   assert_eq!(rows_3_4, [(255, 255), (128, 128), (128, 128), (128, 128), (128, 128), (128, 128), (128, 128), (255, 255)]);
+}
+
+// La nave completa con la misma escena que la ventana (carga, cámara,
+// recorte, iluminación y rasterización)
+fn ship_scene(angle_x: f32, angle_y: f32, shading_mode: ShadingMode, aspect: f32) -> (Model, Renderer, Transform) {
+   let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("models").join("NavePrototipo2.obj");
+   let mut model = Model::load_obj(&path).unwrap();
+   model.compute_smooth_normals(SMOOTHING_ANGLE);
+
+   let camera = Camera::new(
+      Vec3::new(0.0, 0.0, 3.0),
+      Vec3::new(0.0, 0.0, 0.0),
+      Vec3::new(0.0, 1.0, 0.0),
+      45.0_f32.to_radians(),
+      aspect,
+   );
+   let mut renderer = Renderer::new(camera, default_lighting());
+   renderer.shading_mode = shading_mode;
+   // Los propulsores con el color y el brillo que les da la ventana
+   renderer.parts = model.objects.iter()
+      .map(|object| {
+         let engine = ["Circle", "Circle.001"].contains(&object.name.as_str());
+         Part {
+            color: engine.then_some(Color::new(120, 140, 160)),
+            emission: if engine { Vec3::new(3.0, 0.9, 0.2) } else { Vec3::new(0.0, 0.0, 0.0) },
+            groups: vec![Part::default(); object.groups.len()],
+            ..Part::default()
+         }
+      })
+      .collect();
+   let transform = Transform::fit(&model, angle_x.to_radians(), angle_y.to_radians());
+
+   (model, renderer, transform)
+}
+
+fn render_ship(name: &str, angle_x: f32, angle_y: f32, shading_mode: ShadingMode) {
+   let (model, renderer, transform) = ship_scene(angle_x, angle_y, shading_mode, 320.0 / 240.0);
+   let mut framebuffer = blank(320, 240);
+   renderer.render(&model, &transform, &mut framebuffer);
+   check(name, &framebuffer);
+}
+
+// Giro sobre el eje de la vista (alabeo), escala no uniforme y desplazamiento,
+// que no se podían expresar solo con los ángulos en X e Y
+#[test]
+fn ship_roll_stretched() {
+   let (model, renderer, mut transform) = ship_scene(-90.0, 180.0, ShadingMode::Phong, 320.0 / 240.0);
+   transform.rotate(Vec3::new(0.0, 0.0, 1.0), 30.0_f32.to_radians());
+   transform.scale = Vec3::new(transform.scale.x * 1.4, transform.scale.y * 0.8, transform.scale.z);
+   transform.translation = Vec3::new(0.2, -0.1, -0.3);
+   let mut framebuffer = blank(320, 240);
+   renderer.render(&model, &transform, &mut framebuffer);
+   check("ship_roll_stretched", &framebuffer);
+}
+
+// Un fragment shader propio conectado al renderizador: visualización de normales
+#[test]
+fn ship_normals_shader() {
+   let (model, mut renderer, transform) = ship_scene(-70.0, 140.0, ShadingMode::Phong, 320.0 / 240.0);
+   renderer.fragment_shader = Box::new(NormalShader);
+   let mut framebuffer = blank(320, 240);
+   renderer.render(&model, &transform, &mut framebuffer);
+   check("ship_normals_shader", &framebuffer);
+}
+
+// El rasterizador por tiles tiene que dar exactamente lo mismo que el serie,
+// también con tiles incompletos en los bordes y con MSAA
+#[test]
+fn ship_tiled_matches_serial() {
+   let (width, height) = (333, 251);
+   let (model, mut renderer, transform) = ship_scene(-70.0, 140.0, ShadingMode::Phong, width as f32 / height as f32);
+
+   for (msaa, hdr) in [(Msaa::Off, false), (Msaa::X4, false), (Msaa::X4, true)] {
+      renderer.msaa = msaa;
+      renderer.hdr = hdr;
+
+      renderer.threads = 1;
+      let mut serial = blank(width, height);
+      renderer.render(&model, &transform, &mut serial);
+
+      renderer.threads = 4;
+      let mut tiled = blank(width, height);
+      renderer.render(&model, &transform, &mut tiled);
+
+      assert!(serial.buffer == tiled.buffer, "el color difiere entre el render serie y por tiles ({})", msaa.name());
+      assert!(serial.z_buffer == tiled.z_buffer, "la profundidad difiere entre el render serie y por tiles ({})", msaa.name());
+   }
+}
+
+#[test]
+fn ship_front_phong() {
+   render_ship("ship_front_phong", -90.0, 180.0, ShadingMode::Phong);
+}
+
+#[test]
+fn ship_front_flat() {
+   render_ship("ship_front_flat", -90.0, 180.0, ShadingMode::Flat);
+}
+
+#[test]
+fn ship_front_gouraud() {
+   render_ship("ship_front_gouraud", -90.0, 180.0, ShadingMode::Gouraud);
+}
+
+#[test]
+fn ship_three_quarter() {
+   render_ship("ship_three_quarter", -70.0, 140.0, ShadingMode::Phong);
+}
+
+#[test]
+fn ship_side() {
+   render_ship("ship_side", -90.0, 90.0, ShadingMode::Phong);
+}
+
+#[test]
+fn ship_stats_count_every_face() {
+   // De frente la nave entra completa en la vista: cada cara se dibuja como un
+   // triángulo o se descarta por mirar hacia atrás
+   let (model, renderer, transform) = ship_scene(-90.0, 180.0, ShadingMode::Phong, 4.0 / 3.0);
+   let mut framebuffer = blank(160, 120);
+   let stats = renderer.render(&model, &transform, &mut framebuffer);
+   assert_eq!(stats.triangles_drawn + stats.triangles_culled, model.faces.len());
+   assert!(stats.triangles_drawn > 0 && stats.triangles_culled > 0, "{:?}", stats);
+}
+
+// Cámara pegada al casco: muchas caras y aristas cruzan el plano cercano y
+// tienen que recortarse sin estirarse hacia el infinito ni desaparecer
+#[test]
+fn ship_camera_near_hull() {
+   let (model, mut renderer, transform) = ship_scene(-70.0, 140.0, ShadingMode::Phong, 320.0 / 240.0);
+   renderer.camera.eye = Vec3::new(0.25, 0.15, 0.6);
+   renderer.render_mode = RenderMode::SolidWireframe;
+   let mut framebuffer = blank(320, 240);
+   let stats = renderer.render(&model, &transform, &mut framebuffer);
+   assert!(stats.triangles_drawn > 0, "{:?}", stats);
+   check("ship_camera_near_hull", &framebuffer);
+}
+
+#[test]
+fn ship_hidden_line() {
+   // Solo las aristas visibles, gruesas y sin repetir, sobre un fondo de color
+   let (model, mut renderer, transform) = ship_scene(-70.0, 140.0, ShadingMode::Phong, 320.0 / 240.0);
+   renderer.render_mode = RenderMode::HiddenLine;
+   renderer.line_style = LineStyle { color: Color::new(255, 255, 255), width: 2.0, cap: LineCap::Round, antialiased: true };
+   let mut framebuffer = blank(320, 240);
+   framebuffer.clear(Color::new(30, 30, 60));
+   renderer.render(&model, &transform, &mut framebuffer);
+   check("ship_hidden_line", &framebuffer);
+}
+
+#[test]
+fn ship_parts() {
+   // Un propulsor se saca hacia atrás con su propia transformación y el casco
+   // se pinta de rojo desde su grupo; el resto de la nave no cambia
+   let (model, mut renderer, transform) = ship_scene(-70.0, 140.0, ShadingMode::Phong, 320.0 / 240.0);
+   let index = |name: &str| model.objects.iter().position(|o| o.name == name).unwrap();
+   renderer.parts[index("Circle.001")].transform.translation = Vec3::new(0.0, 0.0, -0.4);
+   renderer.parts[index("Plane")].groups[0].color = Some(Color::new(200, 60, 60));
+   let mut framebuffer = blank(320, 240);
+   renderer.render(&model, &transform, &mut framebuffer);
+   check("ship_parts", &framebuffer);
+}
+
+#[test]
+fn ship_msaa() {
+   // Solo las caras, para que se vean los bordes suavizados de la silueta
+   let (model, mut renderer, transform) = ship_scene(-70.0, 140.0, ShadingMode::Phong, 320.0 / 240.0);
+   renderer.render_mode = RenderMode::Solid;
+   renderer.msaa = Msaa::X4;
+   let mut framebuffer = blank(320, 240);
+   renderer.render(&model, &transform, &mut framebuffer);
+   check("ship_msaa", &framebuffer);
+}
+
+#[test]
+fn msaa_edge_coverage() {
+   // Un borde vertical que pasa por el centro de la columna 8 deja dentro dos
+   // de las cuatro muestras de cada pixel de esa columna, y cada pixel se
+   // sombrea una sola vez aunque tenga varias muestras cubiertas
+   let mut framebuffer = blank(16, 16);
+   framebuffer.set_msaa(Msaa::X4);
+   framebuffer.begin_samples();
+   let mut shaded = 0;
+   draw_triangle(&mut framebuffer, &plain(-20.0, -20.0, 0.5), &plain(8.5, -20.0, 0.5), &plain(8.5, 40.0, 0.5), 1.0, |_| {
+      shaded += 1;
+      Color::new(255, 255, 255)
+   });
+   framebuffer.resolve();
+
+   assert_eq!(shaded, 9 * 16);
+   for y in 0..16 {
+      let row = &framebuffer.buffer[y * 16..(y + 1) * 16];
+      assert_eq!(row[7], Color::new(255, 255, 255));
+      assert_eq!(row[8], Color::new(128, 128, 128));
+      assert_eq!(row[9], Color::new(0, 0, 0));
+   }
+}
+
+#[test]
+fn ship_hdr() {
+   // Los propulsores brillan con más luz de la que cabe en 8 bits; con ACES
+   // conservan la gradación en vez de quedar de un color plano
+   let (model, mut renderer, transform) = ship_scene(-70.0, 140.0, ShadingMode::Phong, 320.0 / 240.0);
+   renderer.hdr = true;
+   renderer.tone_mapper = ToneMapper { tone_mapping: ToneMapping::Aces, exposure: 0.0 };
+   let mut framebuffer = blank(320, 240);
+   framebuffer.clear(Color::new(30, 34, 50));
+   renderer.render(&model, &transform, &mut framebuffer);
+   check("ship_hdr", &framebuffer);
+}
+
+#[test]
+fn hdr_keeps_background() {
+   // El fondo pasa a luz de la escena y vuelve sin cambiar de color
+   for msaa in [Msaa::Off, Msaa::X4] {
+      let mut framebuffer = blank(8, 8);
+      for (i, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+         *pixel = Color::new((i * 4) as u8, (255 - i * 3) as u8, (i * 37 % 256) as u8);
+      }
+      let expected = framebuffer.buffer.clone();
+      let tone_mapper = ToneMapper { tone_mapping: ToneMapping::Reinhard, exposure: 1.0 };
+
+      framebuffer.set_msaa(msaa);
+      framebuffer.set_hdr(true);
+      framebuffer.begin_hdr(&tone_mapper);
+      framebuffer.resolve_hdr(&tone_mapper);
+      assert!(framebuffer.buffer == expected, "el fondo cambió con HDR ({})", msaa.name());
+   }
+}
+
+#[test]
+fn fxaa_triangle() {
+   // Bordes en escalera de un triángulo sin MSAA, suavizados por FXAA
+   let mut framebuffer = blank(64, 64);
+   draw_triangle(&mut framebuffer, &plain(6.0, 10.0, 0.5), &plain(58.0, 22.0, 0.5), &plain(20.0, 58.0, 0.5), 1.0, |_| {
+      Color::new(230, 230, 230)
+   });
+   let post = PostChain { passes: vec![PostPass { effect: PostEffect::Fxaa, enabled: true }], threads: 2 };
+   post.apply(&mut framebuffer);
+   check("fxaa_triangle", &framebuffer);
+}
+
+#[test]
+fn ship_post_chain() {
+   // Todos los efectos encendidos, en el orden por defecto
+   let (model, renderer, transform) = ship_scene(-70.0, 140.0, ShadingMode::Phong, 320.0 / 240.0);
+   let mut framebuffer = blank(320, 240);
+   framebuffer.clear(Color::new(40, 44, 60));
+   renderer.render(&model, &transform, &mut framebuffer);
+   let mut post = PostChain::standard();
+   for pass in &mut post.passes {
+      pass.enabled = true;
+   }
+   post.apply(&mut framebuffer);
+   check("ship_post_chain", &framebuffer);
+}
+
+#[test]
+fn post_passes_keep_flat_images() {
+   // Sin bordes no hay nada que suavizar ni que realzar, y la tabla identidad
+   // no cambia los colores; una cadena con todo apagado no toca la imagen
+   let mut framebuffer = blank(16, 12);
+   framebuffer.clear(Color::new(90, 140, 200));
+   let expected = framebuffer.buffer.clone();
+
+   let mut post = PostChain::standard();
+   post.apply(&mut framebuffer);
+   assert!(framebuffer.buffer == expected);
+
+   post.set_lut(Lut::identity(9));
+   for pass in &mut post.passes {
+      pass.enabled = matches!(pass.effect, PostEffect::Fxaa | PostEffect::Sharpen { .. } | PostEffect::ColorGrading(_));
+   }
+   post.apply(&mut framebuffer);
+   assert!(framebuffer.buffer == expected, "los efectos cambiaron una imagen uniforme");
+}
+